@@ -0,0 +1,81 @@
+use std::str::FromStr;
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+use dnstoolkit::types::name::Name;
+
+fn from_text(c: &mut Criterion) {
+    let mut group = c.benchmark_group("from_text");
+
+    // The ascii fast path against the full IDNA processing of the unicode labels
+    group.bench_function("ascii", |b| {
+        b.iter(|| Name::from_text(black_box("www.example.com.")).unwrap())
+    });
+    group.bench_function("ascii_ace", |b| {
+        b.iter(|| Name::from_ace(black_box("www.example.com.")).unwrap())
+    });
+    group.bench_function("idn", |b| {
+        b.iter(|| Name::from_text(black_box("www.தமிழ்.example.com.")).unwrap())
+    });
+    group.finish();
+}
+
+fn wire(c: &mut Criterion) {
+    let name = Name::from_str("www.example.com.").unwrap();
+    let mut wire = Vec::new();
+    name.to_wire(&mut wire).unwrap();
+
+    let mut compressed = wire.clone();
+    compressed.extend_from_slice(b"\x04mail\xc0\x04");
+
+    c.bench_function("from_wire", |b| {
+        b.iter(|| Name::from_wire(black_box(&wire), 0).unwrap())
+    });
+    c.bench_function("from_wire_compressed", |b| {
+        b.iter(|| Name::from_wire(black_box(&compressed), 17).unwrap())
+    });
+    c.bench_function("to_wire", |b| {
+        let mut out = Vec::with_capacity(64);
+        b.iter(|| {
+            out.clear();
+            black_box(&name).to_wire(&mut out).unwrap();
+        })
+    });
+}
+
+fn eq(c: &mut Criterion) {
+    let lower = Name::from_str("www.example.com.").unwrap();
+    let mixed = Name::from_ace("WWW.Example.COM.").unwrap();
+    let other = Name::from_str("www.example.org.").unwrap();
+
+    let mut group = c.benchmark_group("eq");
+    group.bench_function("same_case", |b| b.iter(|| black_box(&lower) == black_box(&lower)));
+    group.bench_function("mixed_case", |b| b.iter(|| black_box(&lower) == black_box(&mixed)));
+    group.bench_function("different", |b| b.iter(|| black_box(&lower) == black_box(&other)));
+    group.finish();
+}
+
+fn labels(c: &mut Criterion) {
+    let text = b"a.b.c.d.www.example.com.";
+    let warm = unsafe { Name::from_bytes_raw(text).unwrap() };
+    warm.label_boundaries();
+
+    // The boundary cache saves the scans for the dots on the repeated label accesses. The
+    // first access includes building the name, so "build_only" is the baseline to
+    // subtract from it.
+    let mut group = c.benchmark_group("labels");
+    group.bench_function("build_only", |b| {
+        b.iter(|| unsafe { Name::from_bytes_raw(black_box(text)).unwrap() })
+    });
+    group.bench_function("first_access", |b| {
+        b.iter(|| {
+            let name = unsafe { Name::from_bytes_raw(black_box(text)).unwrap() };
+            name.labels().len()
+        })
+    });
+    group.bench_function("cached_access", |b| b.iter(|| black_box(&warm).labels().len()));
+    group.finish();
+}
+
+criterion_group!(benches, from_text, wire, eq, labels);
+criterion_main!(benches);
@@ -1,9 +1,7 @@
 #[macro_use]
 extern crate lazy_static;
 
-mod types;
-
-use types::name::Name;
+pub mod types;
 
 
 #[cfg(test)]
@@ -0,0 +1,195 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use smallvec::SmallVec;
+
+use super::name::Name;
+
+/// Identifier of a suffix stored in a [`NameInterner`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SuffixId(u32);
+
+/// A name interned in a [`NameInterner`], made of its leftmost label and the identifier of
+/// the interned suffix holding the remaining labels.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct InternedName {
+    prefix: SmallVec<[u8; 16]>,
+    suffix: Option<SuffixId>,
+    absolute: bool,
+}
+
+impl InternedName {
+    /// Returns the identifier of the interned suffix, None for the names with at most
+    /// one label.
+    #[inline]
+    pub fn suffix(&self) -> Option<SuffixId> {
+        self.suffix
+    }
+}
+
+/// Identifier of a label stored in a [`NameInterner`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct LabelId(u32);
+
+#[derive(Debug)]
+struct Suffix {
+    label: LabelId,
+    parent: Option<SuffixId>,
+}
+
+/// A pool storing the suffixes shared by many names only once.
+///
+/// Each suffix is stored as its leftmost label and a link to its own interned suffix, so
+/// the names `www.example.com` and `mail.example.com` share the storage of `example.com`,
+/// which in turn shares the storage of `com` with every other `.com` name. The labels
+/// themselves are stored once too, so `example` is shared by `example.com` and
+/// `example.org`.
+///
+/// This trades CPU, to materialize the names back, for memory when holding millions of
+/// names with common suffixes, like in a resolver cache. The labels are stored as given,
+/// so the names round-trip exactly, including the case.
+#[derive(Debug, Default)]
+pub struct NameInterner {
+    suffixes: Vec<Suffix>,
+    lookup: HashMap<(Option<SuffixId>, LabelId), SuffixId>,
+    /// The label bytes are shared with `label_ids`, so each label is held only once.
+    labels: Vec<Arc<[u8]>>,
+    label_ids: HashMap<Arc<[u8]>, LabelId>,
+}
+
+impl NameInterner {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Interns the suffix of the name (all but the leftmost label) and returns the
+    /// interned name.
+    pub fn intern(&mut self, name: &Name) -> InternedName {
+        let labels = name.labels();
+        let labels: Vec<&[u8]> = labels.iter()
+            .map(|v| &**v)
+            .filter(|v| !v.is_empty())
+            .collect();
+
+        let mut suffix = None;
+        for label in labels.iter().skip(1).rev() {
+            suffix = Some(self.intern_suffix(suffix, label));
+        }
+
+        InternedName {
+            prefix: labels.first().map_or_else(SmallVec::new, |v| SmallVec::from_slice(v)),
+            suffix,
+            absolute: name.is_absolute(),
+        }
+    }
+
+    fn intern_suffix(&mut self, parent: Option<SuffixId>, label: &[u8]) -> SuffixId {
+        let key = (parent, self.intern_label(label));
+        if let Some(id) = self.lookup.get(&key) {
+            return *id;
+        }
+
+        let id = SuffixId(self.suffixes.len() as u32);
+        self.suffixes.push(Suffix {
+            label: key.1,
+            parent,
+        });
+        self.lookup.insert(key, id);
+        id
+    }
+
+    fn intern_label(&mut self, label: &[u8]) -> LabelId {
+        if let Some(id) = self.label_ids.get(label) {
+            return *id;
+        }
+
+        let id = LabelId(self.labels.len() as u32);
+        let label: Arc<[u8]> = Arc::from(label);
+        self.labels.push(label.clone());
+        self.label_ids.insert(label, id);
+        id
+    }
+
+    /// Materializes the interned name back to a name.
+    ///
+    /// The interned name must have been created by this interner.
+    pub fn resolve(&self, name: &InternedName) -> Name {
+        let mut value = Vec::with_capacity(64);
+        value.extend_from_slice(&name.prefix);
+
+        let mut suffix = name.suffix;
+        while let Some(SuffixId(id)) = suffix {
+            let entry = &self.suffixes[id as usize];
+            value.push(b'.');
+            value.extend_from_slice(&self.labels[entry.label.0 as usize]);
+            suffix = entry.parent;
+        }
+
+        if name.absolute {
+            value.push(b'.');
+        }
+
+        // This is safe because the labels are taken from valid names as is
+        unsafe { Name::from_bytes_raw(&value).unwrap() }
+    }
+
+    /// Returns the number of suffixes stored.
+    #[inline]
+    pub fn suffix_count(&self) -> usize {
+        self.suffixes.len()
+    }
+
+    /// Returns the number of distinct labels stored for the suffixes.
+    #[inline]
+    pub fn label_count(&self) -> usize {
+        self.labels.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+    use crate::types::name::{EMPTY, ROOT};
+
+    #[test]
+    fn round_trip() {
+        let mut interner = NameInterner::new();
+
+        for name in &["www.example.com.", "www.Example.COM", "com", "a.b.c.d.example.org."] {
+            let name = Name::from_ace(name).unwrap();
+            let interned = interner.intern(&name);
+            assert!(interner.resolve(&interned).case_matches(&name));
+        }
+
+        let interned = interner.intern(&ROOT);
+        assert_eq!(interner.resolve(&interned), *ROOT);
+        let interned = interner.intern(&EMPTY);
+        assert_eq!(interner.resolve(&interned), *EMPTY);
+    }
+
+    #[test]
+    fn shared_suffix_storage() {
+        let mut interner = NameInterner::new();
+
+        let www = interner.intern(&Name::from_str("www.example.com").unwrap());
+        // example.com and com
+        assert_eq!(interner.suffix_count(), 2);
+
+        let mail = interner.intern(&Name::from_str("mail.example.com").unwrap());
+        assert_eq!(interner.suffix_count(), 2);
+        assert_eq!(www.suffix(), mail.suffix());
+
+        // example.org and org are added, reusing the stored example label
+        let org = interner.intern(&Name::from_str("www.example.org").unwrap());
+        assert_eq!(interner.suffix_count(), 4);
+        assert_eq!(interner.label_count(), 3);
+        assert_ne!(www.suffix(), org.suffix());
+        assert_eq!(interner.resolve(&org).to_string(), "www.example.org");
+
+        let other = interner.intern(&Name::from_str("other.com").unwrap());
+        assert_eq!(interner.suffix_count(), 4);
+        assert_eq!(interner.resolve(&other).to_string(), "other.com");
+    }
+}
@@ -1 +1,5 @@
-pub mod name;
\ No newline at end of file
+pub mod interner;
+pub mod name;
+pub mod psl;
+pub mod suffix_set;
+pub mod trie;
\ No newline at end of file
@@ -1,14 +1,25 @@
 use std::convert::TryFrom;
 use std::fmt::Display;
+use std::fmt::Write as _;
 use std::str::FromStr;
 use std::string::FromUtf8Error;
 
+use rand::Rng;
 use smallvec::alloc::fmt::Formatter;
 use smallvec::SmallVec;
 use thiserror::Error;
 use std::ops::Deref;
-use idna::Config;
-use std::any::Any;
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::sync::OnceLock;
+use std::hash::{Hash, Hasher};
+use std::net::{IpAddr, Ipv4Addr};
+#[cfg(feature = "ipnetwork")]
+use std::net::Ipv6Addr;
+
+use super::psl::PublicSuffixList;
 
 
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
@@ -22,9 +33,21 @@ impl<'a> Display for Label<'a> {
     }
 }
 
-#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+impl<'a> Deref for Label<'a> {
+    type Target = [u8];
+
+    fn deref(&self) -> &Self::Target {
+        self.value
+    }
+}
+
+#[derive(Clone)]
 pub struct Name {
-    value: SmallVec<[u8; 36]>
+    value: SmallVec<[u8; 36]>,
+    /// The offsets of the `.` separators in `value`, computed on the first use so the
+    /// repeated label accesses don't scan the name again. The offsets fit in u8 as a name
+    /// is at most 255 bytes long.
+    boundaries: OnceLock<SmallVec<[u8; 8]>>,
 }
 
 /// The errors that can happen when parsing
@@ -32,11 +55,15 @@ pub struct Name {
 /// 1. &str to Name
 /// 2. &[u8] to Name
 ///
+/// The IDNA errors are broken out into `InvalidPunycode` and `DisallowedCodepoint`
+/// where possible, with `IDNAError` as the catch-all for the rest.
 #[derive(Debug, Error)]
 #[non_exhaustive]
 pub enum NameParseError {
-    #[error("IDNAError")]
-    IDNAError(#[from] idna::Errors),
+    #[error("IDNAError: {0}")]
+    IDNAError(idna::Errors),
+    #[error("Name '{0}' has a disallowed codepoint")]
+    DisallowedCodepoint(String),
     #[error("Utf8Error: {0}")]
     Utf8Error(#[from] FromUtf8Error),
     #[error("Name '{0}' is larger than 255 characters")]
@@ -44,25 +71,217 @@ pub enum NameParseError {
     #[error("Label '{0}' is larger than 63 characters")]
     LabelTooLong(String),
     #[error("EmptyLabel at position '{0}'")]
-    EmptyLabel(usize)
+    EmptyLabel(usize),
+    #[error("Buffer of size '{0}' is too small, '{1}' bytes required")]
+    BufferTooSmall(usize, usize),
+    #[error("Empty name can't be encoded in wire format")]
+    EmptyName,
+    #[error("Unexpected end of wire data at offset '{0}'")]
+    UnexpectedEnd(usize),
+    #[error("Unsupported label type '{0:#04x}' at offset '{1}'")]
+    InvalidLabelType(u8, usize),
+    #[error("Byte '{0:#04x}' at offset '{1}' is not allowed in a label")]
+    InvalidLabelByte(u8, usize),
+    #[error("More than '{0}' compression pointers followed")]
+    TooManyPointers(usize),
+    #[error("Top level label '{0}' is all numeric")]
+    NumericTld(String),
+    #[error("Name '{0}' has trailing whitespace")]
+    TrailingWhitespace(String),
+    #[error("Control character '{0:#04x}' at position '{1}'")]
+    ControlCharacter(u8, usize),
+    #[error("Non ascii character at position '{0}'")]
+    NonAscii(usize),
+    #[error("Label '{0}' is not valid punycode")]
+    InvalidPunycode(String),
+    #[error("Compression pointer at offset '{0}' is not allowed")]
+    CompressionNotAllowed(usize),
+    #[error("Name '{0}' is absolute")]
+    AbsoluteName(String),
+    #[error("Name '{0}' is relative")]
+    RelativeName(String),
+    #[error("Name has '{0}' labels, more than allowed '{1}'")]
+    TooManyLabels(usize, usize),
+    #[error("Offset '{0}' is beyond the range of the compression pointers")]
+    OffsetTooLarge(usize),
+    #[error("Trailing data of '{0}' bytes after the name at offset '{1}'")]
+    TrailingData(usize, usize),
+    #[error("Invalid percent encoding at position '{0}'")]
+    InvalidPercentEncoding(usize)
+}
+
+/// Maximum number of compression pointers followed when parsing a name from wire format
+/// before giving up. This guards against pointer loops.
+pub const MAX_POINTERS: usize = 16;
+
+/// Maximum number of labels, excluding the root label, allowed by
+/// [`Name::from_text_strict`] and [`NameParseOptions::strict`]. This is the most a name
+/// can have within the 255 bytes of the wire format.
+///
+/// The 255 characters text limit alone still lets one more label into a relative name,
+/// like `a.a. ... .a` with 128 labels, which some DNS stacks reject. Other caps can be set
+/// through [`NameParseOptions::max_labels`].
+pub const MAX_LABELS: usize = 127;
+
+/// The largest offset a compression pointer can refer to, as the pointer holds the offset
+/// in 14 bits.
+pub const MAX_COMPRESSION_OFFSET: usize = 0x3fff;
+
+/// Whether a name may be compressed when written by [`Name::to_wire_compressed`].
+///
+/// Compression is only allowed for the owner names, the question names and the names in
+/// the RDATA of the well-known record types of RFC 1035 (RFC 3597 section 4). It is
+/// `Forbidden` for
+///
+/// 1. the RDATA names of all the other record types, including the unknown types
+/// 2. the canonical form of the records used for DNSSEC (RFC 4034 section 6.2)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompressionPolicy {
+    Allowed,
+    Forbidden,
+}
+
+/// The parse policy of [`Name::from_text_with_options`].
+///
+/// The default allows absolute names, rejects the leading dots and has no label count
+/// cap, the same as [`Name::from_text`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NameParseOptions {
+    /// Whether absolute names like `example.com.` are accepted. When false, they are
+    /// rejected with `NameParseError::AbsoluteName`, the root name included.
+    pub allow_absolute: bool,
+    /// Whether a single leading dot like in `.example.com` is accepted and dropped. When
+    /// false, it is rejected with `NameParseError::EmptyLabel`.
+    pub allow_leading_dot: bool,
+    /// The maximum number of labels, excluding the root label. The names with more are
+    /// rejected with `NameParseError::TooManyLabels`. None leaves the count to the length
+    /// limits only.
+    pub max_labels: Option<usize>,
+}
+
+impl NameParseOptions {
+    /// Returns the default options with the label count capped at [`MAX_LABELS`], the cap
+    /// of [`Name::from_text_strict`].
+    pub fn strict() -> Self {
+        NameParseOptions {
+            max_labels: Some(MAX_LABELS),
+            ..Default::default()
+        }
+    }
+}
+
+impl Default for NameParseOptions {
+    fn default() -> Self {
+        NameParseOptions {
+            allow_absolute: true,
+            allow_leading_dot: false,
+            max_labels: None,
+        }
+    }
 }
 
 impl Name {
-    pub fn labels(&self) -> Vec<Label> {
-        let splits = self.value.split(|v| *v == '.' as u8);
-        splits.map(|v| Label { value: v }).collect()
+    /// Builds a name out of the given bytes, with the label boundaries not computed yet.
+    #[inline]
+    fn from_value(value: SmallVec<[u8; 36]>) -> Name {
+        Name {
+            value,
+            boundaries: OnceLock::new(),
+        }
+    }
+
+    pub fn labels(&self) -> Vec<Label<'_>> {
+        self.cached_splits().map(|v| Label { value: v }).collect()
+    }
+
+    /// Returns a borrowed view of the name, whose labels borrow the bytes of self rather
+    /// than the view.
+    #[inline]
+    pub fn as_name_ref(&self) -> NameRef<'_> {
+        NameRef { value: &self.value }
+    }
+
+    /// Returns the labels same as [`Name::labels`] along with the starting byte offset of
+    /// each label in the name.
+    ///
+    /// For `ab.cde.f`, the offsets are 0, 3 and 7.
+    pub fn label_offsets(&self) -> impl Iterator<Item=(usize, Label<'_>)> + '_ {
+        let mut offset = 0;
+        self.value.split(|v| *v == b'.').map(move |v| {
+            let start = offset;
+            offset += v.len() + 1;
+            (start, Label { value: v })
+        })
+    }
+
+    /// Returns the number of labels of the name, excluding the root label.
+    ///
+    /// Unlike `labels().len()`, the empty root label of the absolute names is not counted.
+    /// So `www.example.com.` and `www.example.com` both have 3 labels and `ROOT` has none.
+    pub fn label_count(&self) -> usize {
+        match self.value.as_slice() {
+            b"" | b"." => 0,
+            _ => self.cached_boundaries().len() + !self.is_absolute() as usize
+        }
+    }
+
+    /// Returns the number of length prefixed labels in the wire format of the name,
+    /// excluding the terminating root label.
+    ///
+    /// This is counted by scanning the bytes for the separators rather than from the
+    /// cached label boundaries, so it is a cross-check for [`Name::label_count`]. Every
+    /// label is followed by a separator in the wire format and so are all but the last
+    /// label of a relative name in the text.
+    pub fn wire_label_count(&self) -> usize {
+        let separators = self.value.iter().filter(|v| **v == b'.').count();
+        match self.value.as_slice() {
+            b"" | b"." => 0,
+            _ if self.is_absolute() => separators,
+            _ => separators + 1
+        }
+    }
+
+    /// Checks whether the name is the `EMPTY` name. The `ROOT` name is not empty.
+    ///
+    /// As `Name` derefs to `[u8]`, `name.is_empty()` resolves to the slice method and
+    /// checks for zero bytes rather than asking a question about the name. This method
+    /// states the intent clearly and should be preferred.
+    #[inline]
+    pub fn is_empty_name(&self) -> bool {
+        self.value.is_empty()
     }
 
     pub fn is_absolute(&self) -> bool {
         self.value.len() > 0 && self.value[self.len() - 1] == '.' as u8
     }
 
+    /// Checks whether the name can be sent as a query name by a stub resolver.
+    ///
+    /// The `EMPTY` name and the relative names are not queryable; they have to be
+    /// completed to a fully qualified name, for example with the search list, first.
+    #[inline]
+    pub fn is_queryable(&self) -> bool {
+        !self.is_empty_name() && self.is_absolute()
+    }
+
     /// This function is unsafe because there is no checks made to ensure the given
     /// &[u8] is ascii u8 slice
     ///
     /// This the responsibility of the caller to ensure the &[u8] is a u8 slice of ascii
     /// characters
     pub unsafe fn from_bytes_ascii(name: &[u8]) -> Result<Self, NameParseError> {
+        Self::check_ascii(name)?;
+        Self::from_bytes_raw(name)
+    }
+
+    /// Checks the length, label length and empty label rules of the given name.
+    ///
+    /// This function is unsafe for the same reason as [`Name::from_bytes_ascii`].
+    unsafe fn check_ascii(name: &[u8]) -> Result<(), NameParseError> {
+        // The root name is the only name made of just the empty root label
+        if name == b"." {
+            return Ok(());
+        }
 
         if name.len() > 255 {
             return Err(NameParseError::NameTooLarge(
@@ -83,7 +302,32 @@ impl Name {
             }
         };
 
-        Self::from_bytes_raw(name)
+        Ok(())
+    }
+
+    /// Checks whether all the bytes of the name are ascii, i.e. at most `0x7f`.
+    ///
+    /// The `Display` and `AsRef<str>` implementations rely on this invariant to skip the
+    /// utf-8 validation, so the callers building names through the unsafe constructors
+    /// like [`Name::from_bytes_raw`] can verify it cheaply. See [`Name::validate`] for
+    /// checking all the invariants.
+    #[inline]
+    pub fn is_ascii(&self) -> bool {
+        self.value.is_ascii()
+    }
+
+    /// Checks all the invariants of a name, for auditing the names built through the
+    /// unsafe constructors like [`Name::from_bytes_raw`] which skip the checks.
+    ///
+    /// 1. The name is ascii, `NameParseError::NonAscii` is returned otherwise.
+    /// 2. The length, label length and empty label rules of [`Name::from_bytes_ascii`].
+    pub fn validate(&self) -> Result<(), NameParseError> {
+        if let Some(position) = self.value.iter().position(|v| !v.is_ascii()) {
+            return Err(NameParseError::NonAscii(position));
+        }
+
+        // This is safe because the name is checked to be ascii above
+        unsafe { Self::check_ascii(&self.value) }
     }
 
     /// This function is unsafe because, there is no checks made to ensure the given &[u8]
@@ -98,9 +342,7 @@ impl Name {
     /// not undefined behaviour occurs.
     #[inline]
     pub unsafe fn from_bytes_raw(name: &[u8]) -> Result<Self, NameParseError> {
-        Ok(Name {
-            value: SmallVec::<[u8; 36]>::from(name)
-        })
+        Ok(Name::from_value(SmallVec::<[u8; 36]>::from(name)))
     }
 
     #[inline]
@@ -119,8 +361,15 @@ impl Name {
         Self::from_bytes_ascii(name.as_bytes())
     }
 
+    /// Parses the name from its text form, converting the unicode labels as per IDNA.
+    ///
+    /// Names with trailing ascii whitespace (often carried along when copied from files)
+    /// or with control characters are rejected, as they would otherwise silently become
+    /// part of a label.
     #[inline]
     pub fn from_text(name: &str) -> Result<Self, NameParseError> {
+        Self::check_text(name)?;
+
         let idna = idna::Config::default();
         // Disabling hyphen '-' check on label
         // If set to true, labels starts with and ends with hyphens are marked as errors
@@ -152,195 +401,4943 @@ impl Name {
 
         // Converting unicode string to idna compatible format.
         // Any error occurred will be propagated.
-        let idna_domain = idna::domain_to_ascii(name)?;
+        let idna_domain = idna::domain_to_ascii(name)
+            .map_err(|errors| Self::classify_idna_errors(name, errors))?;
 
         // This is safe because, idna::domain_to_ascii function will return
         // String only with ascii characters
         return unsafe { Self::from_text_ascii(idna_domain.as_str()) }
     }
 
-    // TODO: implement ```fn from_wire(&self, ...)```
-    // TODO: implement ```fn is_wild(&self)```
-    // TODO: implement ```fn fullcompare(&self, other: Self)```
-    // TODO: implement ```fn is_subdomain(&self)```
-    // TODO: implement ```fn is_superdomain(&self)```
-    // TODO: implement ```fn to_text(&self)```
-    // TODO: implement ```fn to_unicode(&self)```
-    // TODO: implement ```fn to_wire(&self, ...)```
-    // TODO: implement ```fn to_digestable(&self, origin: Self)```
-    // TODO: implement ```fn split(&self, depth: usize)```
-    // TODO: implement ```fn concatenate(&self, other: Self)```
-    // TODO: implement ```fn relativize(&self, origin: Self)```
-    // TODO: implement ```fn derelativize(&self, origin: Self)```
-    // TODO: implement ```fn choose_relativity(&self, ...)```
-    // TODO: implement ```fn parent(&self)```
-}
-
-impl Display for Name {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", unsafe {std::str::from_utf8_unchecked(self.value.as_ref())})
+    /// Runs a parse of an internationalized name through the IDNA processing of
+    /// [`Name::from_text`], so the first real parse doesn't pay for bringing the IDNA
+    /// mapping and normalization tables into memory.
+    ///
+    /// Calling this is optional and only useful for the latency sensitive services, at
+    /// their startup. It has no effect on the result of any parse.
+    pub fn warm_up() {
+        let _ = Self::from_text("ẞ-ﬀ.उदाहरण.テスト");
     }
-}
 
-impl FromStr for Name {
-    type Err = NameParseError;
+    /// Parses the name same as [`Name::from_text`], applying the given parse policy.
+    ///
+    /// With `allow_leading_dot`, `.example.com` parses as `example.com`, while `.` is
+    /// still the root name rather than a leading dot.
+    pub fn from_text_with_options(name: &str, options: NameParseOptions)
+                                  -> Result<Self, NameParseError> {
+        let name = match name.strip_prefix('.') {
+            Some(stripped) if options.allow_leading_dot && !stripped.is_empty() => stripped,
+            _ => name
+        };
 
-    fn from_str(name: &str) -> Result<Self, Self::Err> {
-        Self::from_text(name)
-    }
-}
+        let name = Self::from_text(name)?;
+        if !options.allow_absolute && name.is_absolute() {
+            return Err(NameParseError::AbsoluteName(name.to_string()));
+        }
 
-impl TryFrom<&str> for Name {
-    type Error = NameParseError;
-    #[inline]
-    fn try_from(value: &str) -> Result<Self, Self::Error> {
-        value.parse()
+        match options.max_labels {
+            Some(max) if name.label_count() > max => {
+                Err(NameParseError::TooManyLabels(name.label_count(), max))
+            }
+            _ => Ok(name)
+        }
     }
-}
 
-impl TryFrom<&[u8]> for Name {
-    type Error = NameParseError;
+    /// Parses an ascii name whose internationalized labels are already in the ACE
+    /// (punycode) form, skipping the IDNA unicode mapping done by [`Name::from_text`].
+    ///
+    /// Each `xn--` label is validated to decode to unicode, but no label is re-encoded.
+    /// `NameParseError::InvalidPunycode` is returned for the labels which don't decode
+    /// and `NameParseError::NonAscii` for non ascii input.
+    pub fn from_ace(name: &str) -> Result<Self, NameParseError> {
+        Self::check_text(name)?;
 
-    #[inline]
-    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
-        let string = String::from_utf8(Vec::from(value))?;
-        string.parse()
-    }
-}
+        if let Some(position) = name.bytes().position(|v| !v.is_ascii()) {
+            return Err(NameParseError::NonAscii(position));
+        }
 
-impl TryFrom<String> for Name {
-    type Error = NameParseError;
+        for label in name.split('.') {
+            if is_invalid_ace_label(label) {
+                return Err(NameParseError::InvalidPunycode(label.to_string()));
+            }
+        }
 
-    fn try_from(value: String) -> Result<Self, Self::Error> {
-        value.parse()
+        // This is safe because the name is checked to be ascii above
+        unsafe { Self::from_text_ascii(name) }
     }
-}
 
-impl Deref for Name {
-    type Target = [u8];
+    /// Parses the host component of a URL, decoding the percent-encoded bytes before
+    /// processing it same as [`Name::from_text`].
+    ///
+    /// So `%65xample.com` parses as `example.com` and the percent-encoded utf-8 of the
+    /// internationalized hosts is converted as per IDNA. A `%` not followed by two hex
+    /// digits is rejected with `NameParseError::InvalidPercentEncoding` and bytes not
+    /// decoding to utf-8 with `NameParseError::Utf8Error`.
+    pub fn from_url_host(host: &str) -> Result<Self, NameParseError> {
+        let bytes = host.as_bytes();
+        let mut decoded = Vec::with_capacity(bytes.len());
 
-    fn deref(&self) -> &Self::Target {
-        self.value.as_ref()
+        let mut position = 0;
+        while position < bytes.len() {
+            if bytes[position] != b'%' {
+                decoded.push(bytes[position]);
+                position += 1;
+                continue;
+            }
+
+            let byte = bytes.get(position + 1..position + 3)
+                .and_then(|v| std::str::from_utf8(v).ok())
+                .filter(|v| v.bytes().all(|v| v.is_ascii_hexdigit()))
+                .and_then(|v| u8::from_str_radix(v, 16).ok())
+                .ok_or(NameParseError::InvalidPercentEncoding(position))?;
+            decoded.push(byte);
+            position += 3;
+        }
+
+        Self::from_text(&String::from_utf8(decoded)?)
     }
-}
 
-impl AsRef<[u8]> for Name {
-    fn as_ref(&self) -> &[u8] {
-        self
+    /// Maps the opaque IDNA errors of the given name to the specific error variants.
+    ///
+    /// `idna::Errors` doesn't expose the kinds of errors recorded, so the name is checked
+    /// again for the cause instead, first for the `xn--` labels which don't decode and
+    /// then for the disallowed codepoints.
+    fn classify_idna_errors(name: &str, errors: idna::Errors) -> NameParseError {
+        if let Some(label) = name.split('.').find(|v| is_invalid_ace_label(v)) {
+            NameParseError::InvalidPunycode(label.to_string())
+        } else if name.chars().any(is_disallowed_codepoint) {
+            NameParseError::DisallowedCodepoint(name.to_string())
+        } else {
+            NameParseError::IDNAError(errors)
+        }
     }
-}
 
-impl AsRef<str> for Name {
-    fn as_ref(&self) -> &str {
-        // This is safe as we never allow non ascii characters in self.value
-        unsafe {std::str::from_utf8_unchecked(self.value.as_ref())}
+    /// Checks the text form of a name for trailing ascii whitespace and control
+    /// characters.
+    fn check_text(name: &str) -> Result<(), NameParseError> {
+        if name.ends_with(|v: char| v.is_ascii_whitespace()) {
+            return Err(NameParseError::TrailingWhitespace(name.to_string()));
+        }
+
+        if let Some(position) = name.bytes().position(|v| v.is_ascii_control()) {
+            return Err(NameParseError::ControlCharacter(name.as_bytes()[position], position));
+        }
+        Ok(())
     }
-}
 
-lazy_static! {
-    /// Both the below two are safe as all the conditions of Name::from_bytes_raw
-    /// for safe usage are met.
-    ///
-    /// Both the b"." and b""
+    /// Builds a name out of the given labels, leftmost first and excluding the root label.
     ///
-    /// 1. ascii u8 slice or empty u8 slice
-    /// 2. Length is <=255
-    /// 3. Label length is <=63
-    /// 4. Not empty label(other than root)
-    pub static ref ROOT: Name = unsafe { Name::from_bytes_raw(b".").unwrap() };
-    pub static ref EMPTY: Name = unsafe { Name::from_bytes_raw(b"").unwrap() };
-}
+    /// The labels are validated as they are consumed from the iterator, so this stops at
+    /// the first invalid label. The labels must be non empty, at most 63 bytes long and
+    /// ascii without `.`, and the name at most 255 bytes long. No labels with `absolute`
+    /// set gives `ROOT` and without gives `EMPTY`.
+    pub fn from_labels_iter<'a>(labels: impl Iterator<Item=&'a [u8]>, absolute: bool)
+                                -> Result<Name, NameParseError> {
+        let mut value = SmallVec::<[u8; 36]>::new();
 
-#[cfg(test)]
-mod tests_parsing {
-    use super::*;
+        for (position, label) in labels.enumerate() {
+            if label.is_empty() {
+                return Err(NameParseError::EmptyLabel(position));
+            }
 
-    #[test]
-    fn valid_cases_relative() {
-        assert!(Name::from_str("google.com").is_ok());
-        assert!(Name::from_str("www.google.com").is_ok());
-        assert!(Name::from_str("internal.www.facebook.com").is_ok());
-        assert!(Name::from_str("blog.bbc.co.uk").is_ok());
-        assert!(Name::from_str("annauniversity.au").is_ok());
+            let start = value.len() + (position > 0) as usize;
+            if let Some(index) = label.iter().position(|v| !v.is_ascii()) {
+                return Err(NameParseError::NonAscii(start + index));
+            }
+            if let Some(index) = label.iter().position(|v| *v == b'.') {
+                return Err(NameParseError::InvalidLabelByte(b'.', start + index));
+            }
+
+            // This is safe because the label is checked to be ascii above
+            if label.len() > 63 {
+                return Err(NameParseError::LabelTooLong(
+                    unsafe { String::from_utf8_unchecked(label.to_vec()) }));
+            }
+
+            if position > 0 {
+                value.push(b'.');
+            }
+            value.extend_from_slice(label);
+
+            if value.len() + absolute as usize > 255 {
+                return Err(NameParseError::NameTooLarge(
+                    unsafe { String::from_utf8_unchecked(value.to_vec()) }));
+            }
+        }
+
+        if absolute {
+            value.push(b'.');
+        }
+        Ok(Name::from_value(value))
     }
 
-    #[test]
-    fn valid_cases_absolute() {
-        assert!(Name::from_str("google.com.").is_ok());
-        assert!(Name::from_str("www.google.com.").is_ok());
-        assert!(Name::from_str("internal.www.facebook.com.").is_ok());
-        assert!(Name::from_str("blog.bbc.co.uk.").is_ok());
-        assert!(Name::from_str("annauniversity.au.").is_ok());
+    /// Parses the name at the start of the given text, like the owner name of a zone file
+    /// line, and returns it along with the number of bytes consumed.
+    ///
+    /// The name ends at the first whitespace not escaped with a backslash, or at the end
+    /// of the text. So `example.com. 3600 IN A` gives `example.com.` and 12, the offset of
+    /// the space. As the text is in the zone file format, the name is parsed same as
+    /// [`Name::from_masterfile_text`], so `a\ b.com. IN A` gives the name with the escaped
+    /// space in its first label, and the case is preserved.
+    pub fn from_text_prefix(input: &str) -> Result<(Name, usize), NameParseError> {
+        let mut escaped = false;
+        let end = input.char_indices()
+            .find(|(_, c)| {
+                let end = !escaped && c.is_whitespace();
+                escaped = !escaped && *c == '\\';
+                end
+            })
+            .map_or(input.len(), |(position, _)| position);
+
+        Ok((Self::from_masterfile_text(&input[..end])?, end))
     }
 
-    #[test]
-    fn valid_cases_max_limit() {
-        // Max label size allowed
-        assert!(Name::from_str(
-            std::iter::repeat("x").take(63).collect::<String>().as_str()).is_ok());
+    /// Parses the name in the master file presentation format of RFC 1035 section 5.1,
+    /// the reverse of [`Name::to_text`] and [`Name::to_zonefile_text`].
+    ///
+    /// 1. `\DDD` is the byte with the decimal value DDD.
+    /// 2. `\X` is the character X as is, for any other character X.
+    ///
+    /// No IDNA processing is done and the case is preserved, so
+    /// `Name::from_masterfile_text(&name.to_text(false))` gives back the name for any
+    /// name. As the name is stored in the presentation format, the escaped `.` and the
+    /// non ascii bytes can't be held in a label and `NameParseError::InvalidLabelByte` is
+    /// returned for them, with the position of the escape in the text.
+    pub fn from_masterfile_text(text: &str) -> Result<Self, NameParseError> {
+        let bytes = text.as_bytes();
+        let mut value = Vec::with_capacity(bytes.len());
+        let mut position = 0;
 
-        // Max domain size allowed
-        assert!(Name::from_str(
-            std::iter::repeat("x.").take(127).collect::<String>().as_str()).is_ok());
+        while position < bytes.len() {
+            let byte = bytes[position];
+            if !byte.is_ascii() {
+                return Err(NameParseError::NonAscii(position));
+            }
+            if byte != b'\\' {
+                value.push(byte);
+                position += 1;
+                continue;
+            }
+
+            let digits = bytes.get(position + 1..position + 4)
+                .filter(|v| v.iter().all(u8::is_ascii_digit));
+            let (unescaped, len) = match (digits, bytes.get(position + 1)) {
+                (Some(digits), _) => {
+                    let decimal = digits.iter().fold(0u32, |acc, v| acc * 10 + (v - b'0') as u32);
+                    (decimal, 4)
+                }
+                (None, Some(next)) => (*next as u32, 2),
+                (None, None) => return Err(NameParseError::InvalidLabelByte(b'\\', position))
+            };
+
+            if unescaped > 0xff {
+                return Err(NameParseError::InvalidLabelByte(b'\\', position));
+            }
+            let unescaped = unescaped as u8;
+            if unescaped == b'.' || !unescaped.is_ascii() {
+                return Err(NameParseError::InvalidLabelByte(unescaped, position));
+            }
+
+            value.push(unescaped);
+            position += len;
+        }
+
+        // This is safe because only the ascii bytes are let in above
+        unsafe { Self::check_ascii(&value)?; }
+        Ok(Name::from_value(SmallVec::from_vec(value)))
     }
 
-    #[test]
-    fn invalid_cases_emptylabels() {
-        assert!(match Name::from_str("..google.com") {
-            Err(NameParseError::EmptyLabel(pos)) => pos == 0,
-            _ => false
-        });
+    /// Parses the name same as [`Name::from_text`] after cleaning up the accidental
+    /// duplicate dots produced by building names with string concatenation.
+    ///
+    /// 1. Trailing duplicate dots are always trimmed to one, so `example.com..` parses as
+    ///    `example.com.`.
+    /// 2. When `strict` is false, consecutive dots within the name are also collapsed to
+    ///    one, so `a..b` parses as `a.b`. When `strict` is true, such empty interior labels
+    ///    are still rejected with `NameParseError::EmptyLabel`.
+    ///
+    /// Leading dots are never removed and so `.example.com` is always rejected.
+    pub fn from_text_lenient(name: &str, strict: bool) -> Result<Self, NameParseError> {
+        let trimmed = name.trim_end_matches('.');
+        let absolute = trimmed.len() < name.len();
 
-        assert!(match Name::from_str(".google.com") {
-            Err(NameParseError::EmptyLabel(pos)) => pos == 0,
-            _ => false
-        });
+        let mut cleaned = String::with_capacity(name.len());
+        let mut previous = None;
+        for c in trimmed.chars() {
+            if strict || c != '.' || previous != Some('.') || cleaned.is_empty() {
+                cleaned.push(c);
+            }
+            previous = Some(c);
+        }
 
-        assert!(match Name::from_str("www..google.com") {
-            Err(NameParseError::EmptyLabel(pos)) => pos == 1,
-            _ => false
-        });
+        if absolute {
+            cleaned.push('.');
+        }
+        Self::from_text(&cleaned)
     }
 
-    #[test]
-    fn invalid_cases_near_max_limit() {
-        // just above 63 character max limit for a label
-        let long_label = std::iter::repeat("x").take(64).collect::<String>();
-        assert!(match Name::from_str(format!("{}.com", long_label).as_str()) {
-            Err(NameParseError::LabelTooLong(error_label)) => error_label == long_label,
-            _ => false
-        });
+    /// Parses the name same as [`Name::from_text`] with the below additional checks
+    ///
+    /// 1. The top level label must not be all numeric, to avoid ambiguity with
+    ///    IP addresses. `NameParseError::NumericTld` is returned otherwise.
+    /// 2. The name must have at most [`MAX_LABELS`] labels excluding the root label.
+    ///    `NameParseError::TooManyLabels` is returned otherwise. For a different cap, use
+    ///    [`Name::from_text_with_options`] with [`NameParseOptions::max_labels`].
+    pub fn from_text_strict(name: &str) -> Result<Self, NameParseError> {
+        let name = Self::from_text_with_options(name, NameParseOptions::strict())?;
 
-        // just above 255 character max limit for domain name
-        let long_domain = std::iter::repeat("x.").take(128).collect::<String>();
-        assert!(match Name::from_str(long_domain.as_str()) {
-            Err(NameParseError::NameTooLarge(error_name)) => error_name == long_domain,
-            _ => false
-        });
+        if let Some(tld) = name.label_slices().last() {
+            if tld.iter().all(u8::is_ascii_digit) {
+                // This is safe as we never allow non ascii characters in self.value
+                return Err(NameParseError::NumericTld(
+                    unsafe { String::from_utf8_unchecked(tld.to_vec()) }));
+            }
+        }
+        Ok(name)
     }
 
-    #[test]
-    fn not_allowed_unicode_characters() {
-        assert!(match Name::from_str("secure\u{2488}wellsfargo.com") {
-            Err(NameParseError::IDNAError(_errors)) => true,
-            Err(e) => {
-                println!("{:?}", e);
-                false
+    /// Returns the reverse lookup name of the IP address.
+    ///
+    /// 1. `192.0.2.1` gives `1.2.0.192.in-addr.arpa.`
+    /// 2. `2001:db8::1` gives `1.0.0.0 ... 8.b.d.0.1.0.0.2.ip6.arpa.`, a label for each
+    ///    nibble.
+    pub fn from_ip(addr: IpAddr) -> Name {
+        let mut text = String::with_capacity(72);
+        match addr {
+            IpAddr::V4(addr) => {
+                for octet in addr.octets().iter().rev() {
+                    let _ = write!(text, "{}.", octet);
+                }
+                text.push_str("in-addr.arpa.");
             }
-            Ok(v) => {
-                println!("{:?}", v);
-                false
+            IpAddr::V6(addr) => {
+                for octet in addr.octets().iter().rev() {
+                    let _ = write!(text, "{:x}.{:x}.", octet & 0x0f, octet >> 4);
+                }
+                text.push_str("ip6.arpa.");
             }
-        });
-    }
+        }
 
-    #[test]
-    fn allowed_unicode_characters() {
-        assert!(Name::from_str("தமிழ்.wellsfargo.com").is_ok());
+        // This is safe because the text is ascii and within the length limits
+        unsafe { Self::from_text_ascii(&text).unwrap() }
+    }
+
+    /// Returns the RFC 2317 classless reverse delegation zone name of the network the
+    /// address belongs to.
+    ///
+    /// The address is masked to the prefix length. When the prefix ends within an octet,
+    /// the leftmost label is the network's value of that octet followed by `/` and the
+    /// prefix length, so `192.0.2.128/25` gives `128/25.2.0.192.in-addr.arpa.` and
+    /// `192.0.2.32/27` gives `32/27.2.0.192.in-addr.arpa.`. On octet boundaries it is the
+    /// usual reverse zone name, so `192.0.2.0/24` gives `2.0.192.in-addr.arpa.`.
+    ///
+    /// Prefix lengths above 32 are treated as 32.
+    pub fn from_ip_classless(addr: Ipv4Addr, prefix_len: u8) -> Name {
+        let prefix_len = prefix_len.min(32) as u32;
+        let mask = u32::MAX.checked_shl(32 - prefix_len).unwrap_or(0);
+        let octets = (u32::from(addr) & mask).to_be_bytes();
+
+        let full = (prefix_len / 8) as usize;
+        let mut text = String::with_capacity(40);
+        if !prefix_len.is_multiple_of(8) {
+            let _ = write!(text, "{}/{}.", octets[full], prefix_len);
+        }
+        for octet in octets[..full].iter().rev() {
+            let _ = write!(text, "{}.", octet);
+        }
+        text.push_str("in-addr.arpa.");
+
+        // This is safe because the text is ascii and within the length limits
+        unsafe { Self::from_text_ascii(&text).unwrap() }
+    }
+
+    /// Returns the reverse zone names needed to cover the network.
+    ///
+    /// The reverse zones are cut at the octet boundaries for `in-addr.arpa` and at the
+    /// nibble boundaries for `ip6.arpa`. So a network whose prefix ends within an octet
+    /// (nibble) needs one zone for each of the networks of the next boundary it covers,
+    /// like a /23 needing two /24 zones, `2.0.192.in-addr.arpa.` and
+    /// `3.0.192.in-addr.arpa.` for `192.0.2.0/23`. The zones are in the address order.
+    #[cfg(feature = "ipnetwork")]
+    pub fn reverse_zone_for_prefix(net: ipnetwork::IpNetwork) -> Vec<Name> {
+        let prefix = net.prefix() as u32;
+
+        match net.network() {
+            IpAddr::V4(addr) => {
+                let boundary = (prefix + 7) / 8 * 8;
+                let network = u32::from(addr);
+                (0..1u32 << (boundary - prefix))
+                    .map(|i| Ipv4Addr::from(network | i.checked_shl(32 - boundary).unwrap_or(0)))
+                    .map(|v| Self::from_ip(IpAddr::V4(v)).ancestor(4 - boundary as usize / 8))
+                    .map(Option::unwrap)
+                    .collect()
+            }
+            IpAddr::V6(addr) => {
+                let boundary = (prefix + 3) / 4 * 4;
+                let network = u128::from(addr);
+                (0..1u128 << (boundary - prefix))
+                    .map(|i| Ipv6Addr::from(network | i.checked_shl(128 - boundary).unwrap_or(0)))
+                    .map(|v| Self::from_ip(IpAddr::V6(v)).ancestor(32 - boundary as usize / 4))
+                    .map(Option::unwrap)
+                    .collect()
+            }
+        }
+    }
+
+    /// Returns the octets of an `in-addr.arpa` name or the nibbles of an `ip6.arpa` name
+    /// in the address order, or None if the name is not a reverse lookup name.
+    ///
+    /// `1.2.0.192.in-addr.arpa.` gives `[192, 0, 2, 1]` and `8.b.d.0.1.0.0.2.ip6.arpa.`
+    /// gives `[2, 0, 0, 1, 0, 13, 11, 8]`. The names of the reverse zones have fewer
+    /// components, down to none for `in-addr.arpa` itself. The labels must be in the form
+    /// [`Name::from_ip`] writes them, so the octets with leading zeros, the nibbles in
+    /// uppercase and the classless labels like `0/25` give None, as do more than 4 octets
+    /// or 32 nibbles.
+    pub fn reverse_components(&self) -> Option<Vec<u8>> {
+        let labels = self.label_slices();
+        let split = labels.len().checked_sub(2)?;
+        let (components, suffix) = labels.split_at(split);
+
+        let parse: fn(&[u8]) -> Option<u8> = match suffix {
+            [zone, arpa] if arpa.eq_ignore_ascii_case(b"arpa")
+                && zone.eq_ignore_ascii_case(b"in-addr") && components.len() <= 4 => |label| {
+                match label {
+                    [b'0'] => Some(0),
+                    [b'1'..=b'9', ..] if label.len() <= 3 => {
+                        std::str::from_utf8(label).ok()?.parse().ok()
+                    }
+                    _ => None
+                }
+            },
+            [zone, arpa] if arpa.eq_ignore_ascii_case(b"arpa")
+                && zone.eq_ignore_ascii_case(b"ip6") && components.len() <= 32 => |label| {
+                match label {
+                    [digit @ b'0'..=b'9'] => Some(digit - b'0'),
+                    [digit @ b'a'..=b'f'] => Some(digit - b'a' + 10),
+                    _ => None
+                }
+            },
+            _ => return None
+        };
+
+        components.iter().rev().map(|v| parse(v)).collect()
+    }
+
+    /// Returns the sequence of names to be queried for QNAME minimization (RFC 9156).
+    ///
+    /// The sequence starts from the top level label and adds one label at a time until
+    /// the full name is reached. The root is not part of the sequence as it is always
+    /// assumed to be known.
+    ///
+    /// For `a.b.example.com` the steps are
+    ///
+    /// 1. com
+    /// 2. example.com
+    /// 3. b.example.com
+    /// 4. a.b.example.com
+    pub fn qname_minimization_steps(&self) -> Vec<Name> {
+        self.label_starts()
+            .iter()
+            .rev()
+            .map(|start| self.suffix_from(*start))
+            .collect()
+    }
+
+    /// Parses the name in wire format starting at the given offset of the buffer.
+    ///
+    /// Compression pointers are followed, up to [`MAX_POINTERS`] of them. Along with the
+    /// name, the offset just after the name in the buffer is returned. That is, for a
+    /// compressed name, the offset after the first pointer.
+    ///
+    /// The root name, a lone `0x00`, is parsed as `.` returning offset + 1.
+    ///
+    /// As the name is stored in the presentation format, labels with `.` or non ascii
+    /// bytes are not supported and `NameParseError::InvalidLabelByte` is returned.
+    pub fn from_wire(buf: &[u8], offset: usize) -> Result<(Name, usize), NameParseError> {
+        Self::parse_wire(buf, offset, true, MAX_POINTERS)
+    }
+
+    /// Parses the name in wire format same as [`Name::from_wire`], following at most
+    /// `max_pointers` compression pointers instead of [`MAX_POINTERS`].
+    ///
+    /// This lets the security sensitive contexts tighten the limit, down to 0 to reject
+    /// every pointer. `NameParseError::TooManyPointers` is returned with the given limit
+    /// when the name needs more pointers.
+    pub fn from_wire_with_limit(buf: &[u8], offset: usize, max_pointers: usize)
+                                -> Result<(Name, usize), NameParseError> {
+        Self::parse_wire(buf, offset, true, max_pointers)
+    }
+
+    /// Parses the name in wire format from the given `bytes::Bytes` buffer same as
+    /// [`Name::from_wire`], for the packet parsers built on the `bytes` crate.
+    #[cfg(feature = "bytes")]
+    pub fn from_wire_bytes(buf: &bytes::Bytes, offset: usize)
+                           -> Result<(Name, usize), NameParseError> {
+        Self::from_wire(buf, offset)
+    }
+
+    /// Returns the uncompressed wire format of the name as `bytes::Bytes`, same as
+    /// [`Name::to_wire`].
+    #[cfg(feature = "bytes")]
+    pub fn to_bytes(&self) -> Result<bytes::Bytes, NameParseError> {
+        let mut out = Vec::with_capacity(self.wire_len());
+        self.to_wire(&mut out)?;
+        Ok(bytes::Bytes::from(out))
+    }
+
+    /// Returns a set of interesting wire format names to seed the fuzzers of the wire
+    /// format parsers with.
+    ///
+    /// 1. the root name
+    /// 2. a single label name
+    /// 3. a name with the maximum number of labels, 127 single byte labels
+    /// 4. a name compressed with a pointer to a preceding name
+    /// 5. pointer loops, a pointer to itself and two pointers to each other
+    pub fn corpus_seeds() -> Vec<Vec<u8>> {
+        let mut max_labels = b"\x01a".repeat(127);
+        max_labels.push(0);
+
+        vec![
+            b"\x00".to_vec(),
+            b"\x03com\x00".to_vec(),
+            max_labels,
+            b"\x07example\x03com\x00\x03www\xc0\x00".to_vec(),
+            b"\xc0\x00".to_vec(),
+            b"\x01a\xc0\x04\x01b\xc0\x00".to_vec(),
+        ]
+    }
+
+    /// Parses the name in wire format same as [`Name::from_wire`], but errors with
+    /// `NameParseError::CompressionNotAllowed` on encountering a compression pointer.
+    ///
+    /// This is for the contexts where compression is forbidden, like the RDATA of the
+    /// record types defined after RFC 3597.
+    pub fn from_wire_no_compression(buf: &[u8], offset: usize)
+                                    -> Result<(Name, usize), NameParseError> {
+        Self::parse_wire(buf, offset, false, MAX_POINTERS)
+    }
+
+    /// Parses the name in wire format which must occupy the entire buffer, like the
+    /// RDATA made of exactly one name.
+    ///
+    /// `NameParseError::TrailingData` is returned with the number of bytes left over and
+    /// their offset if the name ends before the buffer. As the buffer holds nothing but the
+    /// name, there is nothing for a compression pointer to refer to and so they are
+    /// rejected same as [`Name::from_wire_no_compression`].
+    pub fn from_wire_exact(buf: &[u8]) -> Result<Name, NameParseError> {
+        let (name, end) = Self::parse_wire(buf, 0, false, MAX_POINTERS)?;
+        if end < buf.len() {
+            return Err(NameParseError::TrailingData(buf.len() - end, end));
+        }
+        Ok(name)
+    }
+
+    fn parse_wire(buf: &[u8], offset: usize, compression: bool, max_pointers: usize)
+                  -> Result<(Name, usize), NameParseError> {
+        let mut value = SmallVec::<[u8; 36]>::new();
+        let mut position = offset;
+        let mut end = None;
+        let mut pointers = 0;
+        let mut wire_len = 0;
+
+        loop {
+            let length = *buf.get(position).ok_or(NameParseError::UnexpectedEnd(position))?;
+
+            match length & 0xc0 {
+                0x00 if length == 0 => {
+                    position += 1;
+                    if value.is_empty() {
+                        value.push(b'.');
+                    }
+                    break;
+                }
+                0x00 => {
+                    let start = position + 1;
+                    let label = buf.get(start..start + length as usize)
+                        .ok_or(NameParseError::UnexpectedEnd(buf.len()))?;
+
+                    if let Some(index) = label.iter().position(|v| *v == b'.' || !v.is_ascii()) {
+                        return Err(NameParseError::InvalidLabelByte(label[index], start + index));
+                    }
+
+                    wire_len += label.len() + 1;
+                    if wire_len >= 255 {
+                        value.extend_from_slice(label);
+                        // This is safe because the bytes are checked to be ascii above
+                        return Err(NameParseError::NameTooLarge(
+                            unsafe { String::from_utf8_unchecked(value.to_vec()) }));
+                    }
+
+                    value.extend_from_slice(label);
+                    value.push(b'.');
+                    position = start + label.len();
+                }
+                0xc0 if !compression => {
+                    return Err(NameParseError::CompressionNotAllowed(position));
+                }
+                0xc0 => {
+                    let low = *buf.get(position + 1)
+                        .ok_or(NameParseError::UnexpectedEnd(position + 1))?;
+
+                    pointers += 1;
+                    if pointers > max_pointers {
+                        return Err(NameParseError::TooManyPointers(max_pointers));
+                    }
+
+                    end.get_or_insert(position + 2);
+                    position = ((length as usize & 0x3f) << 8) | low as usize;
+                }
+                _ => return Err(NameParseError::InvalidLabelType(length, position))
+            }
+        }
+
+        Ok((Name::from_value(value), end.unwrap_or(position)))
+    }
+
+    /// Appends the uncompressed wire format of the name to the given buffer.
+    ///
+    /// Each label is written as a length octet followed by the label bytes and the name
+    /// is terminated by the zero length root label. So the root name is encoded as a
+    /// single `0x00`.
+    ///
+    /// Only absolute names have a wire format. `NameParseError::RelativeName` is returned
+    /// for relative names and `NameParseError::EmptyName` for the `EMPTY` name. The names
+    /// whose wire format would exceed 255 bytes are rejected with
+    /// `NameParseError::NameTooLarge`.
+    pub fn to_wire(&self, out: &mut Vec<u8>) -> Result<(), NameParseError> {
+        self.check_wire_encodable()?;
+        self.to_wire_opaque(out);
+        Ok(())
+    }
+
+    /// Returns the canonical wire format of the name used for DNSSEC (RFC 4034 section
+    /// 6.2), i.e. the uncompressed wire format with the ascii letters lowercased.
+    ///
+    /// The labels are lowercased as they are written, so this is the same as
+    /// [`Name::canonical_cow`] followed by [`Name::to_wire`] without the intermediate
+    /// name.
+    /// The errors are the same as of [`Name::to_wire`].
+    pub fn canonical_wire(&self) -> Result<Vec<u8>, NameParseError> {
+        self.check_wire_encodable()?;
+
+        let mut out = Vec::with_capacity(self.wire_len());
+        for label in self.label_slices() {
+            out.push(label.len() as u8);
+            out.extend(label.iter().map(u8::to_ascii_lowercase));
+        }
+        out.push(0);
+        Ok(out)
+    }
+
+    /// Appends the wire format of the name to the given buffer exactly as the name is,
+    /// for the names embedded in RDATA of unknown types (RFC 3597 section 4).
+    ///
+    /// Such names are opaque data. They are never compressed and, unlike the canonical
+    /// form used for DNSSEC, never downcased. Relative names are written as if they were
+    /// absolute, so the `EMPTY` name is encoded as the root name.
+    pub fn to_wire_opaque(&self, out: &mut Vec<u8>) {
+        out.reserve(self.wire_len());
+        for label in self.label_slices() {
+            out.push(label.len() as u8);
+            out.extend_from_slice(label);
+        }
+        out.push(0);
+    }
+
+    /// Checks whether the name has a wire format, that is whether the name is absolute
+    /// and its wire format is at most 255 bytes long.
+    ///
+    /// The wire format is a byte longer than the text, so a 255 characters absolute name,
+    /// for example made by concatenating two names which fit on their own, is valid as text
+    /// but too long for the wire. `NameParseError::NameTooLarge` is returned for it.
+    fn check_wire_encodable(&self) -> Result<(), NameParseError> {
+        if self.value.is_empty() {
+            return Err(NameParseError::EmptyName);
+        }
+
+        if !self.is_absolute() {
+            return Err(NameParseError::RelativeName(self.to_string()));
+        }
+
+        if self.wire_len() > 255 {
+            return Err(NameParseError::NameTooLarge(self.to_string()));
+        }
+        Ok(())
+    }
+
+    /// Appends the uncompressed wire format of the name to the given buffer same as
+    /// [`Name::to_wire`] and invokes `on_write` with the offset in the buffer where the
+    /// name starts.
+    ///
+    /// This lets the callers record where the names landed, for example to build the
+    /// compression offsets or to fix up the lengths later. `on_write` is not invoked when
+    /// the name couldn't be written.
+    pub fn to_wire_tracked(&self, out: &mut Vec<u8>, mut on_write: impl FnMut(usize))
+                           -> Result<(), NameParseError> {
+        let start = out.len();
+        self.to_wire(out)?;
+        on_write(start);
+        Ok(())
+    }
+
+    /// Appends the wire format of the name to the given message buffer, compressing it as
+    /// per the policy.
+    ///
+    /// `compression` maps the names already written to their offsets in the buffer, so
+    /// `out` must hold the message from its first byte. With `CompressionPolicy::Allowed`,
+    /// the longest suffix of the name found in `compression` is replaced by a pointer to it
+    /// and the offsets of the newly written suffixes are added for the names that follow.
+    /// With `CompressionPolicy::Forbidden`, the name is written in full as
+    /// [`Name::to_wire`] does and `compression` is left untouched.
+    ///
+    /// Names are looked up case-insensitively, as per the name equality. A pointer holds
+    /// offsets up to [`MAX_COMPRESSION_OFFSET`] only, so the larger offsets are never
+    /// recorded and are not pointed to even if found in `compression`. A name to be placed
+    /// beyond that offset couldn't be pointed to by the names that follow, and so
+    /// `NameParseError::OffsetTooLarge` is returned for it; such names have to be written
+    /// with `CompressionPolicy::Forbidden`.
+    // The boundary cache is interior mutable, but Hash and Eq only read the wire value.
+    #[allow(clippy::mutable_key_type)]
+    pub fn to_wire_compressed(&self, out: &mut Vec<u8>, compression: &mut HashMap<Name, u16>,
+                              policy: CompressionPolicy) -> Result<(), NameParseError> {
+        if policy == CompressionPolicy::Forbidden {
+            return self.to_wire(out);
+        }
+        self.check_wire_encodable()?;
+
+        if out.len() > MAX_COMPRESSION_OFFSET {
+            return Err(NameParseError::OffsetTooLarge(out.len()));
+        }
+
+        for (start, label) in self.label_starts().into_iter().zip(self.label_slices()) {
+            let suffix = self.suffix_from(start);
+            match compression.get(&suffix) {
+                Some(offset) if *offset as usize <= MAX_COMPRESSION_OFFSET => {
+                    out.extend_from_slice(&(0xc000 | offset).to_be_bytes());
+                    return Ok(());
+                }
+                _ => {}
+            }
+
+            if out.len() <= MAX_COMPRESSION_OFFSET {
+                compression.insert(suffix, out.len() as u16);
+            }
+            out.push(label.len() as u8);
+            out.extend_from_slice(label);
+        }
+        out.push(0);
+        Ok(())
+    }
+
+    /// Returns the number of bytes the name would take when written compressed, given the
+    /// offsets of the names already written to the message, for budgeting the space left
+    /// in a message before writing the name.
+    ///
+    /// `offsets` is the map filled by [`Name::to_wire_compressed`], so the next name can
+    /// be budgeted against the names written so far.
+    ///
+    /// This is the length of the labels before the longest suffix found in `offsets` plus
+    /// 2 bytes for the pointer to it, so a name found as a whole takes just 2 bytes.
+    /// Without such a suffix, this is [`Name::wire_len`]. Same as
+    /// [`Name::to_wire_compressed`], the offsets beyond [`MAX_COMPRESSION_OFFSET`] are not
+    /// pointed to.
+    #[allow(clippy::mutable_key_type)]
+    pub fn compressed_wire_len(&self, offsets: &HashMap<Name, u16>) -> usize {
+        let mut len = 0;
+        for (start, label) in self.label_starts().into_iter().zip(self.label_slices()) {
+            match offsets.get(&self.suffix_from(start)) {
+                Some(offset) if *offset as usize <= MAX_COMPRESSION_OFFSET => return len + 2,
+                _ => len += label.len() + 1
+            }
+        }
+        len + 1
+    }
+
+    /// Writes the uncompressed wire format of the name into the given slice and returns
+    /// the number of bytes written.
+    ///
+    /// Unlike [`Name::to_wire`], no allocation is made. If the slice is too small to hold
+    /// the name, `NameParseError::BufferTooSmall` is returned and the slice is left
+    /// untouched.
+    pub fn write_wire(&self, out: &mut [u8]) -> Result<usize, NameParseError> {
+        self.check_wire_encodable()?;
+
+        let labels = self.label_slices();
+        let required = self.wire_len();
+
+        if out.len() < required {
+            return Err(NameParseError::BufferTooSmall(out.len(), required));
+        }
+
+        let mut position = 0;
+        for label in labels {
+            out[position] = label.len() as u8;
+            out[position + 1..position + 1 + label.len()].copy_from_slice(label);
+            position += label.len() + 1;
+        }
+
+        out[position] = 0;
+        Ok(position + 1)
+    }
+
+    /// Compares the names in the canonical DNSSEC order as per RFC 4034 section 6.1.
+    ///
+    /// The labels are compared from right to left, case-insensitively, as unsigned octet
+    /// sequences. A name with fewer labels sorts before a name having it as the suffix.
+    ///
+    /// When the labels are equal, the relative name sorts before the absolute name.
+    pub fn canonical_cmp(&self, other: &Name) -> Ordering {
+        let ours = self.label_slices();
+        let theirs = other.label_slices();
+
+        for (our, their) in ours.iter().rev().zip(theirs.iter().rev()) {
+            match cmp_ignore_case(our, their) {
+                Ordering::Equal => continue,
+                ordering => return ordering
+            }
+        }
+
+        ours.len().cmp(&theirs.len())
+            .then_with(|| self.is_absolute().cmp(&other.is_absolute()))
+    }
+
+    /// Compares the names by their number of labels first and then in the canonical
+    /// DNSSEC order ([`Name::canonical_cmp`]), so sorting by it gives the shallower names
+    /// first.
+    ///
+    /// `com` sorts before `example.com`, which sorts before `a.example.com` and
+    /// `b.example.org` in that order.
+    pub fn cmp_by_depth(&self, other: &Name) -> Ordering {
+        self.label_count().cmp(&other.label_count())
+            .then_with(|| self.canonical_cmp(other))
+    }
+
+    /// Checks whether the NSEC record of `owner` with the next owner name `next` covers
+    /// the target, proving that the target doesn't exist (RFC 4035 section 5.4).
+    ///
+    /// The target is covered when it sorts strictly between `owner` and `next` in the
+    /// canonical DNSSEC order ([`Name::canonical_cmp`]). The last NSEC record of a zone
+    /// wraps around with `next` being the zone apex, sorting before `owner`, so it covers
+    /// the names sorting after `owner` or before the apex. A record whose `next` is its
+    /// own `owner`, in a zone with a single name, covers every other name.
+    pub fn nsec_covers(owner: &Name, next: &Name, target: &Name) -> bool {
+        let after_owner = owner.canonical_cmp(target) == Ordering::Less;
+        let before_next = target.canonical_cmp(next) == Ordering::Less;
+
+        if owner.canonical_cmp(next) == Ordering::Less {
+            after_owner && before_next
+        } else {
+            after_owner || before_next
+        }
+    }
+
+    /// Checks whether the given names, when sorted by the default `Ord`, are also in the
+    /// canonical DNSSEC order. Returns false if the two orderings diverge.
+    ///
+    /// This is a debugging helper to find sorting bugs when migrating from the byte
+    /// order sorting to the canonical order ([`Name::canonical_cmp`]).
+    pub fn ordering_matches_canonical(names: &[Name]) -> bool {
+        let mut sorted = names.iter().collect::<Vec<&Name>>();
+        sorted.sort();
+        sorted.windows(2).all(|v| v[0].canonical_cmp(v[1]) != Ordering::Greater)
+    }
+
+    /// Feeds the lowercased bytes of the name into the given hasher.
+    ///
+    /// This is consistent with the case-insensitive equality and lets the users hash
+    /// the names with the hasher of their choice. The `Hash` impl delegates to this.
+    pub fn hash_canonical<H: Hasher>(&self, state: &mut H) {
+        for byte in self.value.iter() {
+            state.write_u8(byte.to_ascii_lowercase());
+        }
+        // Terminating with 0xff (never part of a name) for prefix-freedom, same as `str`
+        state.write_u8(0xff);
+    }
+
+    /// Returns the 64 bit FNV-1a hash of the lowercased bytes of the name.
+    ///
+    /// Unlike the `Hash` impl, whose value depends on the hasher, this value is fixed. It
+    /// is the same across the runs, the platforms and the versions of this crate, so it can
+    /// be persisted, for example in hash based indexes. Being consistent with the equality,
+    /// the names differing only in case have the same hash, but `a.com` and `a.com.` don't.
+    ///
+    /// FNV-1a is not resistant to collision attacks; don't use this for the tables keyed
+    /// on the untrusted names.
+    pub fn canonical_hash_u64(&self) -> u64 {
+        fnv1a_ignore_case(&self.value)
+    }
+
+    /// Builds a new name by applying the given transform on each label, excluding the
+    /// root label.
+    ///
+    /// The new name is validated the same way as `TryFrom<Vec<u8>>` and retains the
+    /// absoluteness of self. For example, replacing `com` with `net` in `example.com`
+    /// yields `example.net`.
+    pub fn map_labels(&self, f: impl Fn(Label) -> Vec<u8>) -> Result<Name, NameParseError> {
+        let mut value = self.label_slices().iter()
+            .map(|v| f(Label { value: v }))
+            .collect::<Vec<Vec<u8>>>()
+            .join(&b'.');
+
+        if self.is_absolute() {
+            value.push(b'.');
+        }
+        Name::try_from(value)
+    }
+
+    /// Builds a new name with the rightmost label replaced by the given top level label,
+    /// like `example.com` to `example.test` for rewriting the names in test environments.
+    ///
+    /// The new name is validated same as [`Name::map_labels`] and retains the
+    /// absoluteness of self, so a trailing dot in `new_tld` is ignored. `new_tld` must be a
+    /// single label, `NameParseError::InvalidLabelByte` is returned for any other `.` in it.
+    /// `ROOT` and `EMPTY` have no top level label; `NameParseError::EmptyName` is returned
+    /// for them.
+    pub fn replace_tld(&self, new_tld: &str) -> Result<Name, NameParseError> {
+        let labels = self.label_slices();
+        let (_, rest) = labels.split_last().ok_or(NameParseError::EmptyName)?;
+
+        let new_tld = new_tld.strip_suffix('.').unwrap_or(new_tld);
+        if new_tld.is_empty() {
+            return Err(NameParseError::EmptyLabel(rest.len()));
+        }
+
+        let mut value = rest.join(&b'.');
+        if !rest.is_empty() {
+            value.push(b'.');
+        }
+        if let Some(index) = new_tld.bytes().position(|v| v == b'.') {
+            return Err(NameParseError::InvalidLabelByte(b'.', value.len() + index));
+        }
+        value.extend_from_slice(new_tld.as_bytes());
+
+        if self.is_absolute() {
+            value.push(b'.');
+        }
+        Name::try_from(value)
+    }
+
+    /// Checks whether the uncompressed wire format of the name fits in the given number of
+    /// remaining bytes, for example the space left in a 512 bytes message.
+    ///
+    /// Relative names and the `EMPTY` name have no wire format and so never fit.
+    #[inline]
+    pub fn fits_in(&self, remaining: usize) -> bool {
+        self.is_absolute() && self.wire_len() <= remaining
+    }
+
+    /// Returns the exact length of the uncompressed wire format of the name, i.e. a length
+    /// octet for each label, the label bytes and the terminating root label.
+    ///
+    /// For relative names, which [`Name::to_wire`] rejects, this is the length written by
+    /// [`Name::to_wire_opaque`].
+    pub fn wire_len(&self) -> usize {
+        self.label_slices().iter().map(|v| v.len() + 1).sum::<usize>() + 1
+    }
+
+    /// Checks whether the given uncompressed wire format bytes encode this name,
+    /// case-insensitively, without building a name out of the bytes.
+    ///
+    /// The whole slice must be the encoding of the name. Compression pointers are not
+    /// followed and so never match. Relative names have no wire format and so never match.
+    pub fn eq_wire(&self, wire: &[u8]) -> bool {
+        if !self.is_absolute() {
+            return false;
+        }
+
+        let mut position = 0;
+        for label in self.label_slices() {
+            let start = position + 1;
+            let end = start + label.len();
+            if wire.get(position) != Some(&(label.len() as u8))
+                || !wire.get(start..end).is_some_and(|v| v.eq_ignore_ascii_case(label)) {
+                return false;
+            }
+            position = end;
+        }
+
+        wire.get(position) == Some(&0) && position + 1 == wire.len()
+    }
+
+    /// Returns the ancestor of the name `n` levels up.
+    ///
+    /// `ancestor(0)` is the name itself, `ancestor(1)` is the parent and so on. Removing
+    /// all the labels of an absolute name gives `ROOT` and of a relative name gives
+    /// `EMPTY`. Returns None if `n` exceeds the number of labels.
+    pub fn ancestor(&self, n: usize) -> Option<Name> {
+        let starts = self.label_starts();
+
+        match n.cmp(&starts.len()) {
+            Ordering::Less => Some(self.suffix_from(starts[n])),
+            Ordering::Equal => Some(self.suffix_from(self.value.len() - self.is_absolute() as usize)),
+            Ordering::Greater => None
+        }
+    }
+
+    /// Splits the name after its leftmost `n` labels and returns them as a relative prefix
+    /// along with the remaining suffix, or None if `n` exceeds the number of labels.
+    ///
+    /// For `a.b.c.com` with n=2, it returns (`a.b`, `c.com`). The suffix keeps the
+    /// absoluteness of the name, so splitting off all the labels of an absolute name gives
+    /// `ROOT` as the suffix, and n=0 gives `EMPTY` as the prefix.
+    pub fn split_left(&self, n: usize) -> Option<(Name, Name)> {
+        self.split_ref(n).map(|(prefix, suffix)| (prefix.to_name(), suffix.to_name()))
+    }
+
+    /// Splits the name same as [`Name::split_left`], but returns the prefix and the suffix
+    /// as views borrowing the bytes of self, without allocating.
+    pub fn split_ref(&self, n: usize) -> Option<(NameRef<'_>, NameRef<'_>)> {
+        let starts = self.label_starts();
+
+        let (end, start) = match starts.get(n) {
+            Some(start) if n > 0 => (start - 1, *start),
+            Some(_) => (0, 0),
+            None if n == starts.len() => {
+                let end = self.value.len() - self.is_absolute() as usize;
+                (end, end)
+            }
+            None => return None
+        };
+
+        // The leading labels of a valid name and the bytes from any label start up to the
+        // end of the name also form valid names
+        Some((NameRef { value: &self.value[..end] }, NameRef { value: &self.value[start..] }))
+    }
+
+    /// Returns each label of the name as a relative single label name, excluding the root
+    /// label.
+    ///
+    /// `www.example.com.` gives `www`, `example` and `com`, which, unlike the borrowed
+    /// [`Label`]s, can be used as the keys of a map.
+    pub fn owned_labels(&self) -> Vec<Name> {
+        self.label_slices()
+            .iter()
+            // This is safe because every label of a valid name is a valid name on its own
+            .map(|v| unsafe { Self::from_bytes_raw(v).unwrap() })
+            .collect()
+    }
+
+    /// Checks whether the name is a wildcard name, that is whether its leftmost label is
+    /// `*` (RFC 4592).
+    pub fn is_wild(&self) -> bool {
+        self.label_slices().first() == Some(&&b"*"[..])
+    }
+
+    /// Returns the name with the leftmost `*` label removed, the base name a wildcard
+    /// answer is synthesized from, or None if the name is not a wildcard name.
+    ///
+    /// `*.example.com` gives `example.com`.
+    pub fn wildcard_base(&self) -> Option<Name> {
+        if self.is_wild() {
+            self.ancestor(1)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the name with all the leading `*` labels removed, the encloser used in the
+    /// wildcard NSEC proofs. Names without a leading `*` label are returned unchanged.
+    ///
+    /// `*.example.com` and `*.*.example.com` both give `example.com`.
+    pub fn strip_leading_wildcards(&self) -> Name {
+        let wildcards = self.label_slices().iter().take_while(|v| **v == b"*").count();
+
+        // There is always such an ancestor as the count never exceeds the number of labels
+        self.ancestor(wildcards).unwrap()
+    }
+
+    /// Returns the presentation format of the name.
+    ///
+    /// `\` and `"` are escaped with a backslash and the bytes other than the printable
+    /// ascii characters are escaped as `\DDD`. When `omit_final_dot` is true, the
+    /// trailing dot of absolute names other than the root is left out.
+    pub fn to_text(&self, omit_final_dot: bool) -> String {
+        let mut out = String::with_capacity(self.value.len());
+        self.write_text(&mut out, omit_final_dot);
+        out
+    }
+
+    /// Returns a normalized presentation format of the name, for the outputs which have to
+    /// be stable like the golden files of the snapshot tests.
+    ///
+    /// The name is lowercased, escaped same as [`Name::to_text`] and always ends with the
+    /// trailing dot, so the equivalent names, like `WWW.Example.com` and `www.example.com.`,
+    /// give the same string. The `EMPTY` name gives `.`, same as `ROOT`.
+    pub fn to_stable_string(&self) -> String {
+        let mut out = self.canonical_cow().to_text(false);
+        if !self.is_absolute() {
+            out.push('.');
+        }
+        out
+    }
+
+    /// Appends the presentation format of the name, same as [`Name::to_text`], to the
+    /// given string. This lets the callers reuse the string across names.
+    pub fn write_text(&self, out: &mut String, omit_final_dot: bool) {
+        self.write_escaped(out, omit_final_dot, escape_byte);
+    }
+
+    /// Writes the presentation format of the name, same as [`Name::to_text`], to the given
+    /// writer.
+    ///
+    /// No string is built; the labels are escaped into a buffer on the stack and written
+    /// in runs, so dumping millions of names to a writer doesn't allocate for each name.
+    pub fn write_presentation(&self, w: &mut impl std::io::Write, omit_final_dot: bool)
+                              -> std::io::Result<()> {
+        if self.value.as_slice() == b"." {
+            return w.write_all(b".");
+        }
+
+        for (position, label) in self.label_slices().iter().enumerate() {
+            if position > 0 {
+                w.write_all(b".")?;
+            }
+
+            let mut buffer = EscapeBuffer::new();
+            for byte in label.iter() {
+                if !buffer.has_room() {
+                    buffer.flush(w)?;
+                }
+                escape_byte(*byte, &mut buffer);
+            }
+            buffer.flush(w)?;
+        }
+
+        if self.is_absolute() && !omit_final_dot {
+            w.write_all(b".")?;
+        }
+        Ok(())
+    }
+
+    /// Returns a wrapper displaying the name in the presentation format of
+    /// [`Name::to_text`], computed on the first use and reused after, for the names
+    /// formatted many times.
+    #[inline]
+    pub fn display_cached(&self) -> DisplayCached<'_> {
+        DisplayCached::new(self)
+    }
+
+    /// Returns the presentation format of the name that is safe to use in a master(zone)
+    /// file.
+    ///
+    /// In addition to the escaping done by [`Name::to_text`], the characters having a
+    /// special meaning in master files, `;`, `@`, `$`, `(` and `)` are escaped with a
+    /// backslash. The `EMPTY` name, being the origin itself, is written as `@`.
+    pub fn to_zonefile_text(&self) -> String {
+        if self.value.is_empty() {
+            return "@".to_string();
+        }
+
+        let mut out = String::with_capacity(self.value.len());
+        self.write_escaped(&mut out, false, escape_zonefile_byte);
+        out
+    }
+
+    /// Appends the presentation format of the name escaping each label byte with the
+    /// given function.
+    fn write_escaped(&self, out: &mut String, omit_final_dot: bool, escape: fn(u8, &mut String)) {
+        if self.value.as_slice() == b"." {
+            out.push('.');
+            return;
+        }
+
+        for (position, label) in self.label_slices().iter().enumerate() {
+            if position > 0 {
+                out.push('.');
+            }
+            for byte in label.iter() {
+                escape(*byte, out);
+            }
+        }
+
+        if self.is_absolute() && !omit_final_dot {
+            out.push('.');
+        }
+    }
+
+    /// Checks whether the name is an SVCB/HTTPS owner name using the attrleaf naming
+    /// of RFC 9460 section 2.3. The recognized leftmost label patterns are
+    ///
+    /// 1. `_<port>._<scheme>`, like `_443._https.example.com` or `_853._dns.example.net`,
+    ///    where the port is a decimal number below 65536 and the scheme starts with a
+    ///    letter followed by letters, digits or hyphens.
+    /// 2. `_dns`, like `_dns.resolver.arpa`, as the DNS scheme on the default port
+    ///    (RFC 9461).
+    ///
+    /// The attrleaf labels must be followed by at least one more label. Other underscore
+    /// labels like `_dmarc` are not service bindings.
+    pub fn is_service_binding_owner(&self) -> bool {
+        let labels = self.label_slices();
+
+        let is_scheme = |label: &[u8]| label.len() > 1 && label[0] == b'_'
+            && label[1].is_ascii_alphabetic()
+            && label[2..].iter().all(|v| v.is_ascii_alphanumeric() || *v == b'-');
+
+        let is_port = |label: &[u8]| label.len() > 1 && label[0] == b'_'
+            && label[1..].iter().all(u8::is_ascii_digit)
+            && std::str::from_utf8(&label[1..]).ok()
+                .and_then(|v| v.parse::<u16>().ok()).is_some();
+
+        match labels.as_slice() {
+            [port, scheme, _, ..] if is_port(port) => is_scheme(scheme),
+            [scheme, _, ..] => scheme.eq_ignore_ascii_case(b"_dns"),
+            _ => false
+        }
+    }
+
+    /// Returns a new name with the other name appended to self.
+    ///
+    /// Only a relative name can be concatenated; `NameParseError::AbsoluteName` is
+    /// returned if self is absolute. The result is absolute if the other name is absolute.
+    /// Concatenating with `EMPTY` yields the same name and concatenating with `ROOT` yields
+    /// the absolute form of self.
+    ///
+    /// As self is relative, it never ends with a dot, and the separator is only added
+    /// between two non empty names, so the result never has a doubled root dot. The result
+    /// is checked again, so names built through the unsafe constructors which would form an
+    /// empty label, like `..`, are rejected with `NameParseError::EmptyLabel`.
+    pub fn concatenate(&self, other: &Name) -> Result<Name, NameParseError> {
+        if self.is_absolute() {
+            return Err(NameParseError::AbsoluteName(self.to_string()));
+        }
+
+        let mut value = SmallVec::<[u8; 36]>::from_slice(&self.value);
+        if !value.is_empty() && !other.value.is_empty() && other.value.as_slice() != b"." {
+            value.push(b'.');
+        }
+        value.extend_from_slice(&other.value);
+
+        // This is safe because both the names are ascii
+        unsafe { Self::from_bytes_ascii(&value) }
+    }
+
+    /// Writes self with the other name appended into `out`, same as
+    /// [`Name::concatenate`], replacing the previous value of `out`.
+    ///
+    /// The capacity of `out` is cleared and reused rather than allocating a new name, so a
+    /// single `out` can be reused across the calls in tight loops. On error, `out` is left
+    /// as `EMPTY`.
+    pub fn concat_into(&self, other: &Name, out: &mut Name) -> Result<(), NameParseError> {
+        out.value.clear();
+        out.boundaries = OnceLock::new();
+
+        if self.is_absolute() {
+            return Err(NameParseError::AbsoluteName(self.to_string()));
+        }
+
+        out.value.extend_from_slice(&self.value);
+        if !self.value.is_empty() && !other.value.is_empty() && other.value.as_slice() != b"." {
+            out.value.push(b'.');
+        }
+        out.value.extend_from_slice(&other.value);
+
+        // This is safe because both the names are ascii
+        if let Err(error) = unsafe { Self::check_ascii(&out.value) } {
+            out.value.clear();
+            return Err(error);
+        }
+        Ok(())
+    }
+
+    /// Makes the name absolute in place by appending the origin to it, when the name is
+    /// relative. Absolute names are left unchanged.
+    ///
+    /// Unlike [`Name::concatenate`], no new name is allocated and the existing capacity is
+    /// reused when possible, for bulk loading of zones. The origin must be absolute;
+    /// `NameParseError::RelativeName` is returned otherwise. If the result would be too
+    /// long, the error is returned and self is left unchanged.
+    pub fn make_absolute_with(&mut self, origin: &Name) -> Result<(), NameParseError> {
+        if self.is_absolute() {
+            return Ok(());
+        }
+        if !origin.is_absolute() {
+            return Err(NameParseError::RelativeName(origin.to_string()));
+        }
+
+        let len = self.value.len();
+        self.boundaries = OnceLock::new();
+        if len > 0 && origin.value.as_slice() != b"." {
+            self.value.push(b'.');
+        }
+        self.value.extend_from_slice(&origin.value);
+
+        // This is safe because both the names are ascii
+        if let Err(error) = unsafe { Self::check_ascii(&self.value) } {
+            self.value.truncate(len);
+            return Err(error);
+        }
+        Ok(())
+    }
+
+    /// Parses the given text as a name and concatenates it to self, same as
+    /// [`Name::concatenate`]. So `host.concat_str("example.com.")` works in one call.
+    pub fn concat_str(&self, suffix: &str) -> Result<Name, NameParseError> {
+        self.concatenate(&suffix.parse()?)
+    }
+
+    /// Returns the fully qualified candidate names to query for the name, in the order a
+    /// stub resolver tries them.
+    ///
+    /// For a relative name, it is the name with each search domain appended, followed by
+    /// the bare absolute form of the name. Relative search domains are taken as absolute,
+    /// and the candidates that would be too long are skipped. Absolute names are already
+    /// fully qualified and return just themselves.
+    pub fn complete_with_search(&self, search: &[Name]) -> Vec<Name> {
+        if self.is_absolute() {
+            return vec![self.clone()];
+        }
+
+        search.iter()
+            .chain(std::iter::once(&*ROOT))
+            .filter_map(|domain| self.concatenate(domain).ok())
+            .filter_map(|name| match name.is_absolute() {
+                true => Some(name),
+                false => name.concatenate(&ROOT).ok()
+            })
+            .collect()
+    }
+
+    /// Returns the name relative to the origin, that is self without the labels of the
+    /// origin, or None when self is not equal to or under the origin.
+    ///
+    /// The labels are compared case-insensitively and both the names must be either
+    /// absolute or relative. So `www.example.com.` relative to `example.com.` is `www`,
+    /// the origin itself gives `EMPTY` and `www.example.org.` gives None. This is the
+    /// reverse of [`Name::concatenate`].
+    pub fn try_relativize(&self, origin: &Name) -> Option<Name> {
+        if self.is_absolute() != origin.is_absolute() {
+            return None;
+        }
+
+        let labels = self.label_slices().len();
+        let origin_labels = origin.label_slices().len();
+        if self.common_suffix_len(origin) != origin_labels {
+            return None;
+        }
+
+        let end = if origin_labels == 0 {
+            self.value.len() - self.is_absolute() as usize
+        } else if labels == origin_labels {
+            0
+        } else {
+            // The separator before the first label of the origin
+            self.label_starts()[labels - origin_labels] - 1
+        };
+
+        // This is safe because the leading labels of a valid name also form a valid name
+        Some(unsafe { Self::from_bytes_raw(&self.value[..end]).unwrap() })
+    }
+
+    /// Checks whether the name is the delegation point itself or a name below it, for
+    /// example to find the glue and the out of zone records when loading a zone.
+    ///
+    /// The labels are compared case-insensitively and both the names must be either
+    /// absolute or relative, same as [`Name::try_relativize`]. So `ns1.sub.example.com.`
+    /// and `sub.example.com.` are at or below `sub.example.com.`, but `example.com.` is not.
+    pub fn is_at_or_below(&self, delegation: &Name) -> bool {
+        self.is_absolute() == delegation.is_absolute()
+            && self.shared_suffix_at_least(delegation, delegation.label_count())
+    }
+
+    /// Checks whether the name is within the bailiwick of the zone, i.e. the zone apex or a
+    /// name below it, same as [`Name::is_at_or_below`].
+    ///
+    /// A resolver must discard the glue and authority records for the names outside the
+    /// bailiwick of the zone whose servers sent them, as trusting them allows cache
+    /// poisoning. The match is on whole labels, so `badexample.com.` is not within the
+    /// bailiwick of `example.com.`.
+    #[inline]
+    pub fn in_bailiwick(&self, zone: &Name) -> bool {
+        self.is_at_or_below(zone)
+    }
+
+    /// Checks whether the name is equal to or under any of the given names.
+    ///
+    /// This matches the same way as [`super::suffix_set::SuffixSet`], case-insensitively
+    /// and ignoring the absoluteness, for the small rule lists where building a set is not
+    /// worth it.
+    pub fn matches_any_suffix(&self, suffixes: &[Name]) -> bool {
+        suffixes.iter().any(|v| self.shared_suffix_at_least(v, v.label_count()))
+    }
+
+    /// Checks whether the name is equal to any of the given names, case-insensitively and
+    /// ignoring the absoluteness same as [`Name::matches_any_suffix`].
+    pub fn matches_any_exact(&self, names: &[Name]) -> bool {
+        let count = self.label_count();
+        names.iter().any(|v| v.label_count() == count && self.shared_suffix_at_least(v, count))
+    }
+
+    /// Returns the owner name relative to the zone apex, the part of self left of the zone,
+    /// or None if self is not in the zone.
+    ///
+    /// Same as [`Name::try_relativize`], but the absoluteness of the names is ignored as
+    /// the zones are often configured without the trailing dot. So `www.example.com.` in
+    /// the zone `example.com` gives `www` and the apex gives `EMPTY`.
+    pub fn owner_within_zone(&self, zone: &Name) -> Option<Name> {
+        if self.is_absolute() == zone.is_absolute() {
+            return self.try_relativize(zone);
+        }
+
+        let zone = if zone.is_absolute() {
+            // This is safe because the name without the trailing dot is a valid name
+            unsafe { Self::from_bytes_raw(&zone.value[..zone.value.len() - 1]).unwrap() }
+        } else {
+            zone.concatenate(&ROOT).ok()?
+        };
+        self.try_relativize(&zone)
+    }
+
+    /// Returns the name relative to the origin same as [`Name::try_relativize`], but
+    /// returns a copy of self unchanged when self is not under the origin.
+    pub fn relativize(&self, origin: &Name) -> Name {
+        self.try_relativize(origin).unwrap_or_else(|| self.clone())
+    }
+
+    /// Returns a copy of the name with the case of each ascii letter randomly chosen, as
+    /// done by the DNS 0x20 encoding against spoofing.
+    ///
+    /// The result is still equal to self, as names are compared case-insensitively.
+    pub fn randomize_case(&self, rng: &mut impl Rng) -> Name {
+        Name {
+            value: self.value.iter()
+                .map(|v| if rng.gen() { v.to_ascii_uppercase() } else { v.to_ascii_lowercase() })
+                .collect(),
+            boundaries: self.boundaries.clone(),
+        }
+    }
+
+    /// Checks whether both the names are equal byte for byte, including the case.
+    ///
+    /// Unlike the case-insensitive `PartialEq`, this is for verifying that a response to a
+    /// 0x20 encoded query (see [`Name::randomize_case`]) echoes the exact query casing.
+    #[inline]
+    pub fn case_matches(&self, other: &Name) -> bool {
+        self.value == other.value
+    }
+
+    /// Checks whether both the names are equal as per the canonical DNS equality of RFC
+    /// 4343, ignoring the case of the ascii letters but honoring the trailing dot.
+    ///
+    /// This is the same as `PartialEq`, spelled out for the code where the intent of a
+    /// case-insensitive comparison should be obvious to the reader.
+    #[inline]
+    pub fn eq_ignore_case(&self, other: &Name) -> bool {
+        self.canonical_cmp(other) == Ordering::Equal
+    }
+
+    /// Checks whether the name matches the given glob style pattern, case-insensitively.
+    ///
+    /// 1. A `*` label matches exactly one label, so `*.*.example.com` matches
+    ///    `a.b.example.com` but not `a.example.com`.
+    /// 2. A `?` within a label matches exactly one character of the label, so
+    ///    `web-?.example.com` matches `web-1.example.com`.
+    ///
+    /// This is a helper for the tools filtering names and doesn't follow the DNS wildcard
+    /// semantics of RFC 4592, where `*` matches one or more labels. A trailing dot in the
+    /// pattern or the name is ignored.
+    pub fn matches_glob(&self, pattern: &str) -> bool {
+        let pattern = pattern.strip_suffix('.').unwrap_or(pattern);
+        let labels = self.label_slices();
+
+        if pattern.is_empty() {
+            return labels.is_empty();
+        }
+
+        let patterns = pattern.as_bytes().split(|v| *v == b'.').collect::<SmallVec<[&[u8]; 8]>>();
+        patterns.len() == labels.len()
+            && patterns.iter().zip(labels.iter()).all(|(pattern, label)| {
+                *pattern == b"*" || (pattern.len() == label.len()
+                    && pattern.iter().zip(label.iter())
+                        .all(|(p, l)| *p == b'?' || p.eq_ignore_ascii_case(l)))
+            })
+    }
+
+    /// Checks whether the name matches the DNS name of a TLS certificate, as per the
+    /// name matching rules of RFC 6125 section 6.4.
+    ///
+    /// 1. The labels are compared case-insensitively and a trailing dot is ignored.
+    /// 2. A `*` leftmost label of the certificate name matches exactly one label, so
+    ///    `*.example.com` matches `www.example.com` but neither `a.b.example.com` nor
+    ///    `example.com`.
+    /// 3. The wildcard must be the whole leftmost label and be followed by at least two
+    ///    labels. So the partial label wildcards like `w*.example.com` and the wildcards
+    ///    like `*.com` match nothing.
+    ///
+    /// The certificate name is expected in the ascii (punycode) form.
+    pub fn matches_tls_identity(&self, cert_name: &str) -> bool {
+        let cert_name = cert_name.strip_suffix('.').unwrap_or(cert_name);
+        let labels = self.label_slices();
+
+        if cert_name.is_empty() {
+            return false;
+        }
+
+        let patterns = cert_name.as_bytes().split(|v| *v == b'.').collect::<SmallVec<[&[u8]; 8]>>();
+        patterns.len() == labels.len()
+            && patterns.iter().zip(labels.iter()).enumerate().all(|(position, (pattern, label))| {
+                if position == 0 && *pattern == b"*" {
+                    patterns.len() >= 3
+                } else {
+                    !pattern.contains(&b'*') && pattern.eq_ignore_ascii_case(label)
+                }
+            })
+    }
+
+    /// Checks whether the given unicode text, once IDNA encoded, is equal to the name.
+    ///
+    /// This lets a stored `xn--` name be matched against the unicode typed by the users.
+    /// Returns false if the text is not a valid name.
+    pub fn eq_unicode(&self, unicode: &str) -> bool {
+        Name::from_text(unicode).is_ok_and(|v| v == *self)
+    }
+
+    /// Returns the Levenshtein edit distance between the unicode forms of the names,
+    /// ignoring the case and the trailing dot.
+    ///
+    /// This is meant for flagging the likely typosquats of a name, like `examp1e.com` for
+    /// `example.com` at a distance of 1. The `xn--` labels are decoded to unicode first,
+    /// so the distance counts the characters the user sees rather than the punycode.
+    pub fn label_edit_distance(&self, other: &Name) -> usize {
+        let unicode = |name: &Name| -> Vec<char> {
+            let text = name.canonical_str();
+            let (unicode, _) = idna::domain_to_unicode(text.trim_end_matches('.'));
+            unicode.chars().collect()
+        };
+        levenshtein(&unicode(self), &unicode(other))
+    }
+
+    /// Checks whether each ACE (`xn--`) label of the name decodes to unicode which encodes
+    /// back to the same label, ignoring the case.
+    ///
+    /// A label failing this, like `xn--abc-dec` (`a` followed by a combining diaeresis,
+    /// which IDNA normalizes to `ä` encoded as `xn--bc-uia`), was not produced by IDNA
+    /// processing. It is either malformed or crafted, for example for spoofing. The labels
+    /// which don't decode at all also fail. Non ACE labels always pass.
+    pub fn idna_roundtrips(&self) -> bool {
+        self.label_slices().iter().all(|label| {
+            // This is safe as we never allow non ascii characters in self.value
+            let label = unsafe { std::str::from_utf8_unchecked(label) };
+            if !(label.len() >= 4 && label[..4].eq_ignore_ascii_case("xn--")) {
+                return true;
+            }
+
+            idna::punycode::decode_to_string(&label[4..])
+                .and_then(|unicode| idna::domain_to_ascii(&unicode).ok())
+                .is_some_and(|ace| ace.eq_ignore_ascii_case(label))
+        })
+    }
+
+    /// Returns the registrable domain (eTLD+1) of the name as per the given public suffix
+    /// list.
+    ///
+    /// For `www.bbc.co.uk` with `co.uk` as public suffix, it returns `bbc.co.uk`.
+    ///
+    /// Returns None when the name is itself a public suffix.
+    pub fn registrable_domain(&self, psl: &PublicSuffixList) -> Option<Name> {
+        let starts = self.label_starts();
+        let suffix_len = psl.suffix_len(&self.label_slices());
+
+        if starts.len() <= suffix_len {
+            return None;
+        }
+        Some(self.suffix_from(starts[starts.len() - suffix_len - 1]))
+    }
+
+    /// Returns the number of leading labels, from left to right, shared by both the names.
+    ///
+    /// The labels are compared case-insensitively and the root label is not counted. For
+    /// `a.b.com` and `a.c.com`, it returns 1.
+    pub fn common_prefix_len(&self, other: &Name) -> usize {
+        self.label_slices().iter()
+            .zip(other.label_slices().iter())
+            .take_while(|(a, b)| a.eq_ignore_ascii_case(b))
+            .count()
+    }
+
+    /// Returns the number of trailing labels, from right to left, shared by both the
+    /// names.
+    ///
+    /// The labels are compared case-insensitively and the root label is not counted. For
+    /// `a.example.com` and `b.example.com`, it returns 2.
+    pub fn common_suffix_len(&self, other: &Name) -> usize {
+        self.label_slices().iter().rev()
+            .zip(other.label_slices().iter().rev())
+            .take_while(|(a, b)| a.eq_ignore_ascii_case(b))
+            .count()
+    }
+
+    /// Checks whether the names share at least `min` trailing labels, compared
+    /// case-insensitively from right to left.
+    ///
+    /// Same as `self.common_suffix_len(other) >= min`, but the labels are not collected and
+    /// the comparison stops at the first differing label or once `min` labels matched, so
+    /// it is cheap for the checks like "in the same second level domain" on the hot paths.
+    pub fn shared_suffix_at_least(&self, other: &Name, min: usize) -> bool {
+        self.rev_label_slices()
+            .zip(other.rev_label_slices())
+            .take(min)
+            .take_while(|(a, b)| a.eq_ignore_ascii_case(b))
+            .count() == min
+    }
+
+    /// Returns the labels unique to each name after removing their common suffix.
+    ///
+    /// For `a.b.example.com` and `c.example.com`, it returns `([a, b], [c])`.
+    pub fn symmetric_label_diff<'a>(&'a self, other: &'a Name) -> (Vec<Label<'a>>, Vec<Label<'a>>) {
+        let common = self.common_suffix_len(other);
+        let unique = |name: &'a Name| {
+            let labels = name.label_slices();
+            labels[..labels.len() - common].iter().map(|v| Label { value: v }).collect()
+        };
+        (unique(self), unique(other))
+    }
+
+    /// Splits the name as per the given public suffix list into the subdomain, the
+    /// registrable label and the public suffix.
+    ///
+    /// For `www.blog.example.co.uk` with `co.uk` as public suffix, it returns
+    /// (`www.blog`, `example`, `co.uk`). The subdomain is always relative and is `EMPTY` when
+    /// there are no labels left of the registrable label. The public suffix retains the
+    /// absoluteness of self.
+    ///
+    /// Returns None when the name is itself a public suffix.
+    pub fn dissect(&self, psl: &PublicSuffixList) -> Option<(Name, Label<'_>, Name)> {
+        let starts = self.label_starts();
+        let labels = self.label_slices();
+        let suffix_len = psl.suffix_len(&labels);
+
+        if starts.len() <= suffix_len {
+            return None;
+        }
+
+        let registrable = starts.len() - suffix_len - 1;
+        let subdomain_end = starts[registrable].saturating_sub(1);
+        // This is safe because the labels before any label start form a valid name
+        let subdomain = unsafe { Self::from_bytes_raw(&self.value[..subdomain_end]).unwrap() };
+        let suffix = self.suffix_from(starts[registrable + 1]);
+
+        Some((subdomain, Label { value: labels[registrable] }, suffix))
+    }
+
+    /// Checks whether both the names have the same registrable domain as per the given
+    /// public suffix list. Names which are public suffixes have no registrable domain and
+    /// so never match.
+    ///
+    /// The absoluteness of the names is ignored, so `www.example.com.` and
+    /// `mail.example.com` are in the same registrable domain.
+    pub fn same_registrable_domain(&self, other: &Name, psl: &PublicSuffixList) -> bool {
+        match (self.registrable_domain(psl), other.registrable_domain(psl)) {
+            (Some(ours), Some(theirs)) => {
+                let ours = ours.label_slices();
+                let theirs = theirs.label_slices();
+                ours.len() == theirs.len()
+                    && ours.iter().zip(theirs.iter()).all(|(a, b)| a.eq_ignore_ascii_case(b))
+            }
+            _ => false
+        }
+    }
+
+    /// Returns the shard, in `0..shards`, of the zone the name belongs to, for sharding a
+    /// cache by zone.
+    ///
+    /// The registrable domain of the name as per the given public suffix list is hashed,
+    /// so all the names of a zone, like `www.example.com` and `mail.example.com`, land in
+    /// the same shard. Names which are public suffixes are hashed as a whole. The bytes of
+    /// the domain without the trailing dot are hashed with the lowercased FNV-1a hash of
+    /// [`Name::canonical_hash_u64`], and the shard is that hash modulo `shards`. So the
+    /// shard is case-insensitive, ignores the absoluteness and is the same across the runs,
+    /// the platforms and the versions of this crate.
+    ///
+    /// The list has to be passed in as no list is bundled with this crate (see
+    /// [`PublicSuffixList`]); without it, the zone of `www.bbc.co.uk` couldn't be told
+    /// from that of `www.example.co.uk`. The mapping is only as stable as the list, so
+    /// the callers persisting it must keep using the same list.
+    ///
+    /// Panics if `shards` is zero.
+    pub fn zone_shard(&self, psl: &PublicSuffixList, shards: usize) -> usize {
+        let zone = self.registrable_domain(psl);
+        let zone = zone.as_ref().unwrap_or(self);
+
+        let value = &zone.value[..zone.value.len() - zone.is_absolute() as usize];
+        (fnv1a_ignore_case(value) % shards as u64) as usize
+    }
+
+    /// Returns the cached byte offsets of the `.` separators, computing them on the first
+    /// call. Every label access goes through these, so the name is scanned for the dots
+    /// only once.
+    fn cached_boundaries(&self) -> &[u8] {
+        self.boundaries.get_or_init(|| {
+            self.value.iter().enumerate()
+                .filter(|(_, byte)| **byte == b'.')
+                .map(|(position, _)| position as u8)
+                .collect()
+        })
+    }
+
+    /// Returns the bytes between the separators, the same as splitting the name at the
+    /// dots, but sliced at the cached boundaries.
+    fn cached_splits(&self) -> impl DoubleEndedIterator<Item=&[u8]> + ExactSizeIterator {
+        let value = self.value.as_slice();
+        let boundaries = self.cached_boundaries();
+
+        (0..boundaries.len() + 1).map(move |i| {
+            let start = if i == 0 { 0 } else { boundaries[i - 1] as usize + 1 };
+            let end = boundaries.get(i).map_or(value.len(), |v| *v as usize);
+            &value[start..end]
+        })
+    }
+
+    /// Returns the labels as byte slices from right to left, excluding the root label,
+    /// without collecting them.
+    fn rev_label_slices(&self) -> impl Iterator<Item=&[u8]> {
+        self.cached_splits().take(self.label_count()).rev()
+    }
+
+    /// Returns the labels as byte slices, excluding the root label.
+    ///
+    /// The slices are taken at the cached boundaries and the names with up to 8 labels
+    /// are collected inline, so this neither scans nor allocates.
+    fn label_slices(&self) -> SmallVec<[&[u8]; 8]> {
+        self.cached_splits().take(self.label_count()).collect()
+    }
+
+    /// Returns the byte offsets of each `.` separator in the name, including the trailing
+    /// dot of absolute names.
+    ///
+    /// The bytes after any boundary form a valid name, so the callers can slice the name
+    /// at these offsets. For `a.bb.ccc`, the boundaries are 1 and 4. The offsets are
+    /// copied out of the boundary cache, inline for the names with up to 8 of them.
+    pub fn label_boundaries(&self) -> SmallVec<[usize; 8]> {
+        self.cached_boundaries().iter().map(|v| *v as usize).collect()
+    }
+
+    /// Checks whether the label boundaries of the name are already computed and cached,
+    /// so the label accesses no longer scan the name.
+    ///
+    /// The boundaries are computed on the first call to the methods working on the
+    /// labels, like [`Name::labels`], [`Name::label_boundaries`] or [`Name::ancestor`].
+    #[inline]
+    pub fn has_boundary_cache(&self) -> bool {
+        self.boundaries.get().is_some()
+    }
+
+    /// Returns the number of bytes the name holds on the heap, for the memory profiling
+    /// tools.
+    ///
+    /// The bytes of the names up to 36 bytes long are stored inline and take no heap
+    /// space, longer names take the capacity spilled to the heap. Likewise, the cached
+    /// label boundaries take heap space only for the names with more than 8 of them.
+    pub fn heap_size(&self) -> usize {
+        let value = if self.value.spilled() { self.value.capacity() } else { 0 };
+        let boundaries = match self.boundaries.get() {
+            Some(boundaries) if boundaries.spilled() => boundaries.capacity(),
+            _ => 0
+        };
+        value + boundaries
+    }
+
+    /// Returns the lowercased (canonical) form of the name, borrowing self when the name
+    /// is already lowercase and allocating a lowercased copy only otherwise.
+    pub fn canonical_cow(&self) -> Cow<'_, Name> {
+        if self.value.iter().any(u8::is_ascii_uppercase) {
+            Cow::Owned(self.lowercased())
+        } else {
+            Cow::Borrowed(self)
+        }
+    }
+
+    /// Returns the lowercased presentation form of the name, as a consistent key for
+    /// logging and metrics labels.
+    ///
+    /// Same as [`Name::canonical_cow`], the name is borrowed when already lowercase.
+    pub fn canonical_str(&self) -> Cow<'_, str> {
+        match self.canonical_cow() {
+            Cow::Borrowed(name) => Cow::Borrowed(name.as_ref()),
+            Cow::Owned(name) => Cow::Owned(name.to_string())
+        }
+    }
+
+    /// Returns a copy of the name with all the ascii letters lowercased.
+    fn lowercased(&self) -> Name {
+        Name {
+            value: self.value.iter().map(u8::to_ascii_lowercase).collect(),
+            boundaries: self.boundaries.clone(),
+        }
+    }
+
+    /// Returns the starting byte offset of each label, excluding the root label.
+    fn label_starts(&self) -> SmallVec<[usize; 8]> {
+        let mut starts = SmallVec::new();
+
+        if self.value.is_empty() || self.value.as_slice() == b"." {
+            return starts;
+        }
+
+        starts.push(0);
+        starts.extend(self.cached_boundaries().iter()
+            .map(|v| *v as usize + 1)
+            .filter(|v| *v < self.value.len()));
+        starts
+    }
+
+    /// Builds a new name from the bytes of self starting at the given label start.
+    #[inline]
+    fn suffix_from(&self, start: usize) -> Name {
+        // This is safe because the bytes from any label start up to the end of a valid
+        // name is also a valid name
+        unsafe { Self::from_bytes_raw(&self.value[start..]).unwrap() }
+    }
+
+    // TODO: implement ```fn fullcompare(&self, other: Self)```
+    // TODO: implement ```fn is_subdomain(&self)```
+    // TODO: implement ```fn is_superdomain(&self)```
+    // TODO: implement ```fn to_unicode(&self)```
+    // TODO: implement ```fn to_digestable(&self, origin: Self)```
+    // TODO: implement ```fn split(&self, depth: usize)```
+    // TODO: implement ```fn derelativize(&self, origin: Self)```
+    // TODO: implement ```fn choose_relativity(&self, ...)```
+    // TODO: implement ```fn parent(&self)```
+}
+
+impl Display for Name {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", unsafe {std::str::from_utf8_unchecked(self.value.as_ref())})
+    }
+}
+
+/// Only the bytes are shown, so the output doesn't depend on whether the label
+/// boundaries are cached yet.
+impl std::fmt::Debug for Name {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Name").field("value", &self.value).finish()
+    }
+}
+
+/// Names are compared case-insensitively as per RFC 4343.
+///
+/// Absolute and relative forms of a name are not equal, i.e. `a.com` is not equal to
+/// `a.com.`, so the trailing dot is honored as part of the name.
+impl PartialEq for Name {
+    fn eq(&self, other: &Self) -> bool {
+        // Short-circuit on the byte length before comparing the bytes.
+        //
+        // Case folding never changes the length of an ascii name and the trailing dot is
+        // honored, so names with different byte lengths can never be equal.
+        if self.value.len() != other.value.len() {
+            return false;
+        }
+        self.value.eq_ignore_ascii_case(&other.value)
+    }
+}
+
+impl Eq for Name {}
+
+impl PartialOrd for Name {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Names are ordered by their lowercased bytes to be consistent with the case-insensitive
+/// equality.
+///
+/// The trailing dot is left out of the comparison and only breaks the ties, with the
+/// relative name sorting first, same as [`Name::canonical_cmp`]. So the absolute and
+/// relative forms of a name are always adjacent, `example.com` then `example.com.`, rather
+/// than being split by the names like `example.com-x` whose bytes sort in between.
+impl Ord for Name {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let without_final_dot = |name: &Name| -> usize {
+            name.value.len() - name.is_absolute() as usize
+        };
+
+        cmp_ignore_case(&self.value[..without_final_dot(self)],
+                        &other.value[..without_final_dot(other)])
+            .then_with(|| self.is_absolute().cmp(&other.is_absolute()))
+    }
+}
+
+/// Returns the labels of the wire format name at the given offset of the buffer, leftmost
+/// first and excluding the root label, without building a [`Name`].
+///
+/// The labels are borrowed from the buffer as is. Compression pointers are followed, up to
+/// [`MAX_POINTERS`] of them, same as [`Name::from_wire`]. Unlike [`Name::from_wire`], the
+/// label bytes are not checked, so this also reads the labels a `Name` can't hold. On an
+/// error, the error is yielded and the iteration ends.
+///
+/// This is the cheapest way to read a part of a name, like the top level label.
+pub fn iter_wire_labels(buf: &[u8], offset: usize)
+                        -> impl Iterator<Item=Result<&[u8], NameParseError>> {
+    let mut position = Some(offset);
+    let mut pointers = 0;
+
+    std::iter::from_fn(move || {
+        loop {
+            let current = position?;
+            let result = match buf.get(current) {
+                None => Err(NameParseError::UnexpectedEnd(current)),
+                Some(0) => {
+                    position = None;
+                    return None;
+                }
+                Some(length) if length & 0xc0 == 0x00 => {
+                    let start = current + 1;
+                    match buf.get(start..start + *length as usize) {
+                        Some(label) => {
+                            position = Some(start + label.len());
+                            return Some(Ok(label));
+                        }
+                        None => Err(NameParseError::UnexpectedEnd(buf.len()))
+                    }
+                }
+                Some(length) if length & 0xc0 == 0xc0 => {
+                    pointers += 1;
+                    match buf.get(current + 1) {
+                        None => Err(NameParseError::UnexpectedEnd(current + 1)),
+                        Some(_) if pointers > MAX_POINTERS => {
+                            Err(NameParseError::TooManyPointers(MAX_POINTERS))
+                        }
+                        Some(low) => {
+                            position = Some(((*length as usize & 0x3f) << 8) | *low as usize);
+                            continue;
+                        }
+                    }
+                }
+                Some(length) => Err(NameParseError::InvalidLabelType(*length, current))
+            };
+
+            position = None;
+            return Some(result);
+        }
+    })
+}
+
+/// Returns the number of labels of each length across the given names, the count of the
+/// labels `n` bytes long at index `n`, for analysing the naming patterns of zones.
+///
+/// The root label is not counted, so index 0 is always 0 for the valid names. The labels
+/// of the names built through the unsafe constructors longer than 63 bytes are counted at
+/// index 63.
+pub fn label_length_histogram(names: &[Name]) -> [usize; 64] {
+    let mut histogram = [0; 64];
+    for name in names {
+        for label in name.label_slices() {
+            histogram[label.len().min(63)] += 1;
+        }
+    }
+    histogram
+}
+
+/// Returns the 64 bit FNV-1a hash of the lowercased bytes.
+fn fnv1a_ignore_case(bytes: &[u8]) -> u64 {
+    const OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+    const PRIME: u64 = 0x100000001b3;
+
+    bytes.iter().fold(OFFSET_BASIS, |hash, byte| {
+        (hash ^ byte.to_ascii_lowercase() as u64).wrapping_mul(PRIME)
+    })
+}
+
+/// Checks whether the label is an ACE (`xn--`) label which doesn't decode to unicode.
+fn is_invalid_ace_label(label: &str) -> bool {
+    label.len() >= 4 && label.is_char_boundary(4) && label[..4].eq_ignore_ascii_case("xn--")
+        && idna::punycode::decode_to_string(&label[4..]).is_none_or(|v| v.is_empty())
+}
+
+/// Checks whether the codepoint is disallowed by the IDNA mapping table.
+///
+/// The table isn't public, so the codepoint is converted on its own and after a letter.
+/// A combining mark fails on its own for starting a label and a right to left character
+/// fails after a letter for the bidi rules, but only a disallowed codepoint fails both.
+fn is_disallowed_codepoint(codepoint: char) -> bool {
+    if codepoint.is_ascii() {
+        return false;
+    }
+
+    let mut buf = [b'a'; 5];
+    let len = codepoint.encode_utf8(&mut buf[1..]).len();
+    // These are safe because the buffer holds an ascii letter and an encoded codepoint
+    let alone = unsafe { std::str::from_utf8_unchecked(&buf[1..=len]) };
+    let after_letter = unsafe { std::str::from_utf8_unchecked(&buf[..=len]) };
+    idna::domain_to_ascii(alone).is_err() && idna::domain_to_ascii(after_letter).is_err()
+}
+
+/// Appends the presentation format of a label byte to the given string or buffer.
+///
+/// The errors of the output are ignored, as the callers write to outputs which never
+/// fail.
+#[inline]
+fn escape_byte(byte: u8, out: &mut impl std::fmt::Write) {
+    let _ = match byte {
+        b'\\' | b'"' => out.write_char('\\').and_then(|_| out.write_char(byte as char)),
+        0x21..=0x7e => out.write_char(byte as char),
+        _ => write!(out, "\\{:03}", byte)
+    };
+}
+
+/// A stack buffer collecting the escaped bytes of a label for
+/// [`Name::write_presentation`], so the writer gets whole runs of bytes without a
+/// `String` being built.
+struct EscapeBuffer {
+    bytes: [u8; 64],
+    len: usize,
+}
+
+impl EscapeBuffer {
+    /// The longest escape of a byte, like `\\009`.
+    const MAX_ESCAPE_LEN: usize = 4;
+
+    fn new() -> Self {
+        EscapeBuffer { bytes: [0; 64], len: 0 }
+    }
+
+    /// Checks whether the buffer has room for the escape of one more byte.
+    #[inline]
+    fn has_room(&self) -> bool {
+        self.len + Self::MAX_ESCAPE_LEN <= self.bytes.len()
+    }
+
+    fn flush(&mut self, w: &mut impl std::io::Write) -> std::io::Result<()> {
+        let len = std::mem::replace(&mut self.len, 0);
+        w.write_all(&self.bytes[..len])
+    }
+}
+
+impl std::fmt::Write for EscapeBuffer {
+    fn write_str(&mut self, s: &str) -> std::fmt::Result {
+        let end = self.len + s.len();
+        self.bytes.get_mut(self.len..end).ok_or(std::fmt::Error)?.copy_from_slice(s.as_bytes());
+        self.len = end;
+        Ok(())
+    }
+}
+
+/// Appends the master file presentation format of a label byte to the given string.
+#[inline]
+fn escape_zonefile_byte(byte: u8, out: &mut String) {
+    match byte {
+        b';' | b'@' | b'$' | b'(' | b')' => {
+            out.push('\\');
+            out.push(byte as char);
+        }
+        _ => escape_byte(byte, out)
+    }
+}
+
+/// Returns the minimum number of single character insertions, deletions and
+/// substitutions to turn `a` into `b`.
+fn levenshtein(a: &[char], b: &[char]) -> usize {
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+
+    for (i, x) in a.iter().enumerate() {
+        current[0] = i + 1;
+        for (j, y) in b.iter().enumerate() {
+            let substitution = previous[j] + (x != y) as usize;
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+    previous[b.len()]
+}
+
+/// Compares the given bytes as if both were lowercased.
+#[inline]
+fn cmp_ignore_case(a: &[u8], b: &[u8]) -> Ordering {
+    a.iter().map(u8::to_ascii_lowercase).cmp(b.iter().map(u8::to_ascii_lowercase))
+}
+
+impl Hash for Name {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.hash_canonical(state)
+    }
+}
+
+impl FromStr for Name {
+    type Err = NameParseError;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        Self::from_text(name)
+    }
+}
+
+impl TryFrom<&str> for Name {
+    type Error = NameParseError;
+    #[inline]
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl TryFrom<&[u8]> for Name {
+    type Error = NameParseError;
+
+    #[inline]
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        let string = String::from_utf8(Vec::from(value))?;
+        string.parse()
+    }
+}
+
+/// Takes ownership of the given bytes and uses them as the backing store of the name
+/// when the bytes are ascii, avoiding a copy for names larger than the inline capacity.
+///
+/// The result is the same as `TryFrom<&[u8]>`. The ascii bytes are checked same as
+/// [`Name::from_text`] and lowercased in place, which is all the IDNA processing does to
+/// them. Bytes with non ascii characters or with ACE (`xn--`) labels, which the IDNA
+/// processing validates, are parsed same as `TryFrom<&[u8]>`.
+impl TryFrom<Vec<u8>> for Name {
+    type Error = NameParseError;
+
+    fn try_from(mut value: Vec<u8>) -> Result<Self, Self::Error> {
+        let has_ace_label = value.split(|v| *v == b'.')
+            .any(|v| v.len() >= 4 && v[..4].eq_ignore_ascii_case(b"xn--"));
+        if !value.is_ascii() || has_ace_label {
+            return Self::from_bytes(&value);
+        }
+
+        // This is safe because the bytes are checked to be ascii above
+        Self::check_text(unsafe { std::str::from_utf8_unchecked(&value) })?;
+        value.make_ascii_lowercase();
+        unsafe { Self::check_ascii(&value)?; }
+        Ok(Name::from_value(SmallVec::from_vec(value)))
+    }
+}
+
+impl TryFrom<String> for Name {
+    type Error = NameParseError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl Deref for Name {
+    type Target = [u8];
+
+    fn deref(&self) -> &Self::Target {
+        self.value.as_ref()
+    }
+}
+
+impl AsRef<[u8]> for Name {
+    fn as_ref(&self) -> &[u8] {
+        self
+    }
+}
+
+impl AsRef<str> for Name {
+    fn as_ref(&self) -> &str {
+        // This is safe as we never allow non ascii characters in self.value
+        unsafe {std::str::from_utf8_unchecked(self.value.as_ref())}
+    }
+}
+
+/// A name which compares and hashes by its canonical (lowercased) form but displays as
+/// originally given.
+///
+/// This lets the registrars compare the names the DNS way while showing the casing
+/// chosen by the user, so `Example.COM` and `example.com` are equal but display
+/// differently.
+#[derive(Debug)]
+pub struct CasePreservingName {
+    canonical: Name,
+    original: Name,
+}
+
+impl CasePreservingName {
+    pub fn new(name: Name) -> Self {
+        CasePreservingName {
+            canonical: name.lowercased(),
+            original: name,
+        }
+    }
+
+    /// Returns the lowercased name used for the comparison and hashing.
+    #[inline]
+    pub fn canonical(&self) -> &Name {
+        &self.canonical
+    }
+
+    /// Returns the name as originally given.
+    #[inline]
+    pub fn original(&self) -> &Name {
+        &self.original
+    }
+}
+
+impl From<Name> for CasePreservingName {
+    fn from(name: Name) -> Self {
+        Self::new(name)
+    }
+}
+
+impl PartialEq for CasePreservingName {
+    fn eq(&self, other: &Self) -> bool {
+        self.canonical.value == other.canonical.value
+    }
+}
+
+impl Eq for CasePreservingName {}
+
+impl Hash for CasePreservingName {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.canonical.hash(state)
+    }
+}
+
+impl Display for CasePreservingName {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        self.original.fmt(f)
+    }
+}
+
+/// A name displayed in the presentation format of [`Name::to_text`], with the text
+/// computed once on the first use and reused for all the later uses.
+///
+/// This trades the memory of the text for the CPU of escaping the name each time, for the
+/// names formatted many times, like in a log loop.
+#[derive(Debug)]
+pub struct DisplayCached<'a> {
+    name: &'a Name,
+    text: RefCell<Option<String>>,
+}
+
+impl<'a> DisplayCached<'a> {
+    pub fn new(name: &'a Name) -> Self {
+        DisplayCached {
+            name,
+            text: RefCell::new(None),
+        }
+    }
+
+    /// Returns the name being displayed.
+    #[inline]
+    pub fn name(&self) -> &'a Name {
+        self.name
+    }
+
+    /// Checks whether the text has been computed by an earlier use.
+    #[inline]
+    pub fn has_cached(&self) -> bool {
+        self.text.borrow().is_some()
+    }
+}
+
+impl<'a> Display for DisplayCached<'a> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mut text = self.text.borrow_mut();
+        let text = text.get_or_insert_with(|| self.name.to_text(false));
+        f.write_str(text)
+    }
+}
+
+/// A borrowed view of a [`Name`].
+///
+/// The labels yielded by the view are tied to the lifetime `'a` of the name's bytes and
+/// not to the view, so they outlive a temporary `NameRef`.
+#[derive(Debug, Clone, Copy)]
+pub struct NameRef<'a> {
+    value: &'a [u8]
+}
+
+impl<'a> NameRef<'a> {
+    /// Returns the labels same as [`Name::labels`].
+    pub fn labels(&self) -> impl Iterator<Item=Label<'a>> {
+        self.value.split(|v| *v == b'.').map(|v| Label { value: v })
+    }
+
+    #[inline]
+    pub fn is_absolute(&self) -> bool {
+        self.value.last() == Some(&b'.')
+    }
+
+    /// Returns an owned copy of the name.
+    pub fn to_name(&self) -> Name {
+        // This is safe because the bytes are borrowed from a valid name
+        unsafe { Name::from_bytes_raw(self.value).unwrap() }
+    }
+}
+
+impl<'a> From<&'a Name> for NameRef<'a> {
+    fn from(name: &'a Name) -> Self {
+        name.as_name_ref()
+    }
+}
+
+impl<'a> Display for NameRef<'a> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        // This is safe as the bytes are borrowed from a valid name
+        write!(f, "{}", unsafe {std::str::from_utf8_unchecked(self.value)})
+    }
+}
+
+lazy_static! {
+    /// Both the below two are safe as all the conditions of Name::from_bytes_raw
+    /// for safe usage are met.
+    ///
+    /// Both the b"." and b""
+    ///
+    /// 1. ascii u8 slice or empty u8 slice
+    /// 2. Length is <=255
+    /// 3. Label length is <=63
+    /// 4. Not empty label(other than root)
+    pub static ref ROOT: Name = unsafe { Name::from_bytes_raw(b".").unwrap() };
+    pub static ref EMPTY: Name = unsafe { Name::from_bytes_raw(b"").unwrap() };
+}
+
+#[cfg(test)]
+mod tests_parsing {
+    use super::*;
+
+    #[test]
+    fn valid_cases_relative() {
+        assert!(Name::from_str("google.com").is_ok());
+        assert!(Name::from_str("www.google.com").is_ok());
+        assert!(Name::from_str("internal.www.facebook.com").is_ok());
+        assert!(Name::from_str("blog.bbc.co.uk").is_ok());
+        assert!(Name::from_str("annauniversity.au").is_ok());
+    }
+
+    #[test]
+    fn valid_cases_absolute() {
+        assert!(Name::from_str("google.com.").is_ok());
+        assert!(Name::from_str("www.google.com.").is_ok());
+        assert!(Name::from_str("internal.www.facebook.com.").is_ok());
+        assert!(Name::from_str("blog.bbc.co.uk.").is_ok());
+        assert!(Name::from_str("annauniversity.au.").is_ok());
+    }
+
+    #[test]
+    fn valid_cases_max_limit() {
+        // Max label size allowed
+        assert!(Name::from_str(
+            std::iter::repeat("x").take(63).collect::<String>().as_str()).is_ok());
+
+        // Max domain size allowed
+        assert!(Name::from_str(
+            std::iter::repeat("x.").take(127).collect::<String>().as_str()).is_ok());
+    }
+
+    #[test]
+    fn valid_cases_root() {
+        assert_eq!(Name::from_str(".").unwrap(), *ROOT);
+        assert!(Name::from_str(".").unwrap().is_absolute());
+        assert_eq!(Name::from_str("").unwrap(), *EMPTY);
+    }
+
+    #[test]
+    fn label_count() {
+        for (name, count) in &[("www.example.com.", 3), ("www.example.com", 3), ("com", 1),
+            ("com.", 1), (".", 0), ("", 0)] {
+            let name = Name::from_str(name).unwrap();
+            assert_eq!(name.label_count(), *count);
+            assert_eq!(name.wire_label_count(), name.label_count());
+
+            let mut wire = Vec::new();
+            name.to_wire_opaque(&mut wire);
+            let (mut position, mut labels) = (0, 0);
+            while wire[position] != 0 {
+                position += wire[position] as usize + 1;
+                labels += 1;
+            }
+            assert_eq!(labels, *count);
+        }
+    }
+
+    #[test]
+    fn is_queryable() {
+        assert!(!EMPTY.is_queryable());
+        assert!(!Name::from_str("www.example.com").unwrap().is_queryable());
+        assert!(Name::from_str("www.example.com.").unwrap().is_queryable());
+        assert!(ROOT.is_queryable());
+    }
+
+    #[test]
+    fn validate() {
+        for name in &["www.example.com.", "www.example.com", ".", ""] {
+            assert!(Name::from_str(name).unwrap().validate().is_ok());
+        }
+    }
+
+    #[test]
+    fn is_ascii() {
+        assert!(Name::from_str("www.example.com.").unwrap().is_ascii());
+        assert!(Name::from_text("உதாரணம்.com").unwrap().is_ascii());
+        assert!(ROOT.is_ascii());
+        assert!(EMPTY.is_ascii());
+
+        assert!(!unsafe { Name::from_bytes_raw(b"www.\xe0\xae\xa4.com").unwrap() }.is_ascii());
+        assert!(!unsafe { Name::from_bytes_raw(b"\x80").unwrap() }.is_ascii());
+        assert!(unsafe { Name::from_bytes_raw(b"\x7f").unwrap() }.is_ascii());
+    }
+
+    #[test]
+    fn validate_malformed_raw() {
+        let validate = |name: &[u8]| unsafe { Name::from_bytes_raw(name).unwrap() }.validate();
+
+        assert!(match validate(b"www..example.com") {
+            Err(NameParseError::EmptyLabel(1)) => true,
+            _ => false
+        });
+        assert!(match validate(b".example.com") {
+            Err(NameParseError::EmptyLabel(0)) => true,
+            _ => false
+        });
+        assert!(match validate(b"www.\xe0\xae\xa4.com") {
+            Err(NameParseError::NonAscii(4)) => true,
+            _ => false
+        });
+        assert!(match validate(&[b'a'; 64]) {
+            Err(NameParseError::LabelTooLong(_)) => true,
+            _ => false
+        });
+        assert!(match validate(vec!["a"; 128].join(".").repeat(2).as_bytes()) {
+            Err(NameParseError::NameTooLarge(_)) => true,
+            _ => false
+        });
+    }
+
+    #[test]
+    fn invalid_cases_emptylabels() {
+        assert!(match Name::from_str("..google.com") {
+            Err(NameParseError::EmptyLabel(pos)) => pos == 0,
+            _ => false
+        });
+
+        assert!(match Name::from_str(".google.com") {
+            Err(NameParseError::EmptyLabel(pos)) => pos == 0,
+            _ => false
+        });
+
+        assert!(match Name::from_str("www..google.com") {
+            Err(NameParseError::EmptyLabel(pos)) => pos == 1,
+            _ => false
+        });
+    }
+
+    #[test]
+    fn invalid_cases_near_max_limit() {
+        // just above 63 character max limit for a label
+        let long_label = std::iter::repeat("x").take(64).collect::<String>();
+        assert!(match Name::from_str(format!("{}.com", long_label).as_str()) {
+            Err(NameParseError::LabelTooLong(error_label)) => error_label == long_label,
+            _ => false
+        });
+
+        // just above 255 character max limit for domain name
+        let long_domain = std::iter::repeat("x.").take(128).collect::<String>();
+        assert!(match Name::from_str(long_domain.as_str()) {
+            Err(NameParseError::NameTooLarge(error_name)) => error_name == long_domain,
+            _ => false
+        });
+    }
+
+    #[test]
+    fn not_allowed_unicode_characters() {
+        assert!(match Name::from_str("secure\u{2488}wellsfargo.com") {
+            Err(NameParseError::DisallowedCodepoint(name)) => name == "secure\u{2488}wellsfargo.com",
+            _ => false
+        });
+    }
+
+    #[test]
+    fn valid_cases_byte_vector() {
+        let name = Name::try_from(b"www.google.com.".to_vec()).unwrap();
+        assert_eq!(name.to_string(), "www.google.com.");
+
+        // Larger than the inline capacity
+        let long_name = std::iter::repeat("x.").take(127).collect::<String>();
+        let name = Name::try_from(long_name.clone().into_bytes()).unwrap();
+        assert_eq!(name.to_string(), long_name);
+
+        let name = Name::try_from("தமிழ்.wellsfargo.com".as_bytes().to_vec()).unwrap();
+        assert_eq!(name, Name::from_str("தமிழ்.wellsfargo.com").unwrap());
+    }
+
+    #[test]
+    fn invalid_cases_byte_vector() {
+        let long_domain = std::iter::repeat("x.").take(128).collect::<String>();
+        assert!(match Name::try_from(long_domain.clone().into_bytes()) {
+            Err(NameParseError::NameTooLarge(error_name)) => error_name == long_domain,
+            _ => false
+        });
+
+        assert!(match Name::try_from(b"www..google.com".to_vec()) {
+            Err(NameParseError::EmptyLabel(pos)) => pos == 1,
+            _ => false
+        });
+    }
+
+    #[test]
+    fn byte_vector_matches_slice() {
+        for text in &["WWW.Example.com", "www.google.com.", "xn--bcher-kva.ch", "XN--Bcher-kva.CH.",
+                      "Bücher.ch", "a_b.com", "x"] {
+            let from_vec = Name::try_from(text.as_bytes().to_vec()).unwrap();
+            let from_slice = Name::try_from(text.as_bytes()).unwrap();
+            assert_eq!(from_vec, from_slice);
+            assert_eq!(from_vec.to_string(), from_slice.to_string());
+        }
+        assert_eq!(Name::try_from(b"WWW.Example.com".to_vec()).unwrap().to_string(),
+                   "www.example.com");
+
+        assert!(match Name::try_from(b"exam\tple.com".to_vec()) {
+            Err(NameParseError::ControlCharacter(byte, position)) => byte == b'\t' && position == 4,
+            _ => false
+        });
+        assert!(match Name::try_from(b"example.com ".to_vec()) {
+            Err(NameParseError::TrailingWhitespace(name)) => name == "example.com ",
+            _ => false
+        });
+        assert!(Name::try_from(b"xn--abc.com".to_vec()).is_err());
+        assert!(Name::try_from(b"xn--abc.com".as_ref()).is_err());
+    }
+
+    #[test]
+    fn invalid_cases_whitespace_control() {
+        assert!(match Name::from_str("example.com\n") {
+            Err(NameParseError::TrailingWhitespace(name)) => name == "example.com\n",
+            _ => false
+        });
+
+        assert!(match Name::from_str("example.com\r\n") {
+            Err(NameParseError::TrailingWhitespace(_)) => true,
+            _ => false
+        });
+
+        assert!(match Name::from_str("example.com ") {
+            Err(NameParseError::TrailingWhitespace(name)) => name == "example.com ",
+            _ => false
+        });
+
+        assert!(match Name::from_str("exam\tple.com") {
+            Err(NameParseError::ControlCharacter(b'\t', 4)) => true,
+            _ => false
+        });
+
+        assert!(match Name::from_str("example\x00.com") {
+            Err(NameParseError::ControlCharacter(0, 7)) => true,
+            _ => false
+        });
+    }
+
+    #[test]
+    fn ace_valid() {
+        let name = Name::from_ace("xn--rlcus7b3d.wellsfargo.com").unwrap();
+        assert_eq!(name, Name::from_str("தமிழ்.wellsfargo.com").unwrap());
+
+        let name = Name::from_ace("XN--rlcus7b3d.wellsfargo.com.").unwrap();
+        assert_eq!(name.to_string(), "XN--rlcus7b3d.wellsfargo.com.");
+    }
+
+    #[test]
+    fn ace_invalid() {
+        assert!(match Name::from_ace("xn--99999999999.wellsfargo.com") {
+            Err(NameParseError::InvalidPunycode(label)) => label == "xn--99999999999",
+            _ => false
+        });
+
+        assert!(match Name::from_ace("www.xn--.com") {
+            Err(NameParseError::InvalidPunycode(label)) => label == "xn--",
+            _ => false
+        });
+
+        assert!(match Name::from_ace("தமிழ்.wellsfargo.com") {
+            Err(NameParseError::NonAscii(0)) => true,
+            _ => false
+        });
+    }
+
+    #[test]
+    fn idna_roundtrips() {
+        assert!(Name::from_ace("xn--bcher-kva.example.com").unwrap().idna_roundtrips());
+        assert!(Name::from_ace("XN--rlcus7b3d.example.com.").unwrap().idna_roundtrips());
+        assert!(Name::from_str("bücher.example.com").unwrap().idna_roundtrips());
+        assert!(Name::from_str("www.example.com").unwrap().idna_roundtrips());
+    }
+
+    #[test]
+    fn idna_roundtrips_non_idempotent() {
+        // a + combining diaeresis, normalized to ä by IDNA
+        assert!(!Name::from_ace("xn--abc-dec.example.com").unwrap().idna_roundtrips());
+        // Fullwidth letters, mapped to ascii by IDNA
+        assert!(!Name::from_ace("xn--ri7cmaa8a.example.com").unwrap().idna_roundtrips());
+        // Uppercase Ä, mapped to ä by IDNA
+        assert!(!Name::from_ace("www.xn--bc-3fa.com").unwrap().idna_roundtrips());
+    }
+
+    #[test]
+    fn ace_plain_ascii() {
+        let name = Name::from_ace("www.Google.com").unwrap();
+        // Not mapped through IDNA, so the case is retained
+        assert_eq!(name.to_string(), "www.Google.com");
+        assert_eq!(name, Name::from_str("www.google.com").unwrap());
+
+        assert!(match Name::from_ace("www..google.com") {
+            Err(NameParseError::EmptyLabel(1)) => true,
+            _ => false
+        });
+    }
+
+    #[test]
+    fn from_labels_iter() {
+        let labels = "www example com".split(' ').map(str::as_bytes);
+        let name = Name::from_labels_iter(labels, true).unwrap();
+        assert_eq!(name.to_string(), "www.example.com.");
+
+        let labels = vec![b"www".to_vec(), b"example".to_vec()];
+        let name = Name::from_labels_iter(labels.iter().map(Vec::as_slice), false).unwrap();
+        assert_eq!(name.to_string(), "www.example");
+
+        assert_eq!(Name::from_labels_iter(std::iter::empty(), true).unwrap(), *ROOT);
+        assert_eq!(Name::from_labels_iter(std::iter::empty(), false).unwrap(), *EMPTY);
+    }
+
+    #[test]
+    fn from_labels_iter_invalid() {
+        let labels = std::iter::repeat(&b"x"[..]).take(128);
+        assert!(match Name::from_labels_iter(labels, true) {
+            Err(NameParseError::NameTooLarge(_)) => true,
+            _ => false
+        });
+        // 127 labels fit in 255 bytes with the trailing dot
+        assert!(Name::from_labels_iter(std::iter::repeat(&b"x"[..]).take(127), true).is_ok());
+
+        let long_label = [b'x'; 64];
+        assert!(match Name::from_labels_iter(vec![&long_label[..]].into_iter(), false) {
+            Err(NameParseError::LabelTooLong(label)) => label.len() == 64,
+            _ => false
+        });
+
+        assert!(match Name::from_labels_iter(vec![&b"www"[..], b"", b"com"].into_iter(), false) {
+            Err(NameParseError::EmptyLabel(1)) => true,
+            _ => false
+        });
+
+        assert!(match Name::from_labels_iter(vec![&b"www"[..], b"a.b"].into_iter(), false) {
+            Err(NameParseError::InvalidLabelByte(b'.', 5)) => true,
+            _ => false
+        });
+
+        assert!(match Name::from_labels_iter(vec![&b"\xff"[..]].into_iter(), false) {
+            Err(NameParseError::NonAscii(0)) => true,
+            _ => false
+        });
+    }
+
+    #[test]
+    fn from_text_prefix() {
+        let (name, end) = Name::from_text_prefix("example.com. 3600 IN A").unwrap();
+        assert_eq!(name.to_string(), "example.com.");
+        assert_eq!(end, 12);
+
+        let (name, end) = Name::from_text_prefix("www.example.com\t3600").unwrap();
+        assert_eq!(name.to_string(), "www.example.com");
+        assert_eq!(end, 15);
+
+        let (name, end) = Name::from_text_prefix("example.com.").unwrap();
+        assert_eq!(name.to_string(), "example.com.");
+        assert_eq!(end, 12);
+
+        assert_eq!(Name::from_text_prefix(" example.com").unwrap(), (EMPTY.clone(), 0));
+        assert!(match Name::from_text_prefix("a..b IN A") {
+            Err(NameParseError::EmptyLabel(1)) => true,
+            _ => false
+        });
+    }
+
+    #[test]
+    fn from_text_prefix_escaped_whitespace() {
+        let (name, end) = Name::from_text_prefix("a\\ b.com. 3600 IN A").unwrap();
+        assert_eq!(name.as_ref() as &[u8], b"a b.com.");
+        assert_eq!(name.to_text(false), "a\\032b.com.");
+        assert_eq!(end, 9);
+
+        let (name, end) = Name::from_text_prefix("a\\032b.com\\\t IN A").unwrap();
+        assert!(name.case_matches(&unsafe { Name::from_bytes_raw(b"a b.com\t").unwrap() }));
+        assert_eq!(end, 12);
+
+        let (name, _) = Name::from_text_prefix("WWW.Example.com. IN A").unwrap();
+        assert_eq!(name.to_string(), "WWW.Example.com.");
+    }
+
+    #[test]
+    fn lenient_duplicate_dots() {
+        assert_eq!(Name::from_text_lenient("example.com..", true).unwrap().to_string(),
+                   "example.com.");
+        assert_eq!(Name::from_text_lenient("example.com...", false).unwrap().to_string(),
+                   "example.com.");
+        assert_eq!(Name::from_text_lenient("example.com", true).unwrap().to_string(),
+                   "example.com");
+
+        assert!(match Name::from_text_lenient("a..b", true) {
+            Err(NameParseError::EmptyLabel(1)) => true,
+            _ => false
+        });
+        assert_eq!(Name::from_text_lenient("a..b", false).unwrap().to_string(), "a.b");
+        assert_eq!(Name::from_text_lenient("a...b..", false).unwrap().to_string(), "a.b.");
+
+        assert!(match Name::from_text_lenient("..a.b", false) {
+            Err(NameParseError::EmptyLabel(0)) => true,
+            _ => false
+        });
+        assert_eq!(Name::from_text_lenient("..", true).unwrap(), *ROOT);
+    }
+
+    #[test]
+    fn warm_up() {
+        let inputs = ["www.example.com.", "உதாரணம்.com", "Bücher.example", "xn--ls8h.la",
+            "a\u{2488}b.com", ".example.com"];
+        let parse = || inputs.iter()
+            .map(|v| Name::from_text(v).map(|v| v.to_string()).map_err(|v| v.to_string()))
+            .collect::<Vec<Result<String, String>>>();
+
+        let cold = parse();
+        Name::warm_up();
+        assert_eq!(parse(), cold);
+        Name::warm_up();
+        assert_eq!(parse(), cold);
+    }
+
+    #[test]
+    fn from_url_host() {
+        assert_eq!(Name::from_url_host("%65xample.com").unwrap().to_string(), "example.com");
+        assert_eq!(Name::from_url_host("www.%45XAMPLE%2ecom.").unwrap().to_string(),
+                   "www.example.com.");
+        assert_eq!(Name::from_url_host("example.com").unwrap().to_string(), "example.com");
+        assert_eq!(Name::from_url_host("caf%C3%A9.example").unwrap().to_string(),
+                   "xn--caf-dma.example");
+    }
+
+    #[test]
+    fn from_url_host_invalid() {
+        assert!(match Name::from_url_host("%6xample.com") {
+            Err(NameParseError::InvalidPercentEncoding(0)) => true,
+            _ => false
+        });
+        assert!(match Name::from_url_host("example.com%") {
+            Err(NameParseError::InvalidPercentEncoding(11)) => true,
+            _ => false
+        });
+        assert!(match Name::from_url_host("example.com%2") {
+            Err(NameParseError::InvalidPercentEncoding(11)) => true,
+            _ => false
+        });
+        assert!(match Name::from_url_host("ex%+1ample.com") {
+            Err(NameParseError::InvalidPercentEncoding(2)) => true,
+            _ => false
+        });
+        assert!(match Name::from_url_host("caf%C3.example") {
+            Err(NameParseError::Utf8Error(_)) => true,
+            _ => false
+        });
+        assert!(match Name::from_url_host("example.com%0a") {
+            Err(NameParseError::TrailingWhitespace(_)) => true,
+            _ => false
+        });
+    }
+
+    #[test]
+    fn options_allow_absolute() {
+        let options = NameParseOptions::default();
+        assert!(Name::from_text_with_options("example.com.", options).unwrap().is_absolute());
+        assert_eq!(Name::from_text_with_options(".", options).unwrap(), *ROOT);
+
+        let options = NameParseOptions { allow_absolute: false, ..Default::default() };
+        assert!(match Name::from_text_with_options("example.com.", options) {
+            Err(NameParseError::AbsoluteName(name)) => name == "example.com.",
+            _ => false
+        });
+        assert!(match Name::from_text_with_options(".", options) {
+            Err(NameParseError::AbsoluteName(_)) => true,
+            _ => false
+        });
+        assert_eq!(Name::from_text_with_options("example.com", options).unwrap().to_string(),
+                   "example.com");
+    }
+
+    #[test]
+    fn options_allow_leading_dot() {
+        let options = NameParseOptions::default();
+        assert!(match Name::from_text_with_options(".example.com", options) {
+            Err(NameParseError::EmptyLabel(0)) => true,
+            _ => false
+        });
+
+        let options = NameParseOptions { allow_leading_dot: true, ..Default::default() };
+        assert_eq!(Name::from_text_with_options(".example.com", options).unwrap().to_string(),
+                   "example.com");
+        assert_eq!(Name::from_text_with_options(".example.com.", options).unwrap().to_string(),
+                   "example.com.");
+        assert_eq!(Name::from_text_with_options(".", options).unwrap(), *ROOT);
+        // Only a single leading dot is dropped
+        assert!(match Name::from_text_with_options("..example.com", options) {
+            Err(NameParseError::EmptyLabel(0)) => true,
+            _ => false
+        });
+
+        let options = NameParseOptions {
+            allow_absolute: false,
+            allow_leading_dot: true,
+            ..Default::default()
+        };
+        assert!(match Name::from_text_with_options(".example.com.", options) {
+            Err(NameParseError::AbsoluteName(_)) => true,
+            _ => false
+        });
+    }
+
+    #[test]
+    fn strict_numeric_tld() {
+        assert!(match Name::from_text_strict("example.123") {
+            Err(NameParseError::NumericTld(tld)) => tld == "123",
+            _ => false
+        });
+        assert!(match Name::from_text_strict("example.123.") {
+            Err(NameParseError::NumericTld(tld)) => tld == "123",
+            _ => false
+        });
+
+        assert!(Name::from_text_strict("example.com").is_ok());
+        assert!(Name::from_text_strict("123.com").is_ok());
+        assert!(Name::from_text_strict("example.c0m").is_ok());
+        assert!(Name::from_text("example.123").is_ok());
+    }
+
+    #[test]
+    fn strict_label_count() {
+        let labels_127 = vec!["a"; 127].join(".");
+        assert_eq!(Name::from_text_strict(&labels_127).unwrap().labels().len(), 127);
+        assert!(Name::from_text_strict(&format!("{}.", labels_127)).is_ok());
+
+        let labels_128 = vec!["a"; 128].join(".");
+        assert!(match Name::from_text_strict(&labels_128) {
+            Err(NameParseError::TooManyLabels(128, MAX_LABELS)) => true,
+            _ => false
+        });
+        assert_eq!(Name::from_text(&labels_128).unwrap().labels().len(), 128);
+    }
+
+    #[test]
+    fn options_max_labels() {
+        let labels_127 = vec!["a"; 127].join(".");
+        let labels_128 = vec!["a"; 128].join(".");
+
+        let options = NameParseOptions::strict();
+        assert_eq!(options.max_labels, Some(MAX_LABELS));
+        assert_eq!(Name::from_text_with_options(&labels_127, options).unwrap().label_count(), 127);
+        assert!(match Name::from_text_with_options(&labels_128, options) {
+            Err(NameParseError::TooManyLabels(128, MAX_LABELS)) => true,
+            _ => false
+        });
+
+        // Configurable below the default cap, and unlimited by default
+        let options = NameParseOptions { max_labels: Some(2), ..Default::default() };
+        assert!(Name::from_text_with_options("example.com.", options).is_ok());
+        assert!(match Name::from_text_with_options("www.example.com.", options) {
+            Err(NameParseError::TooManyLabels(3, 2)) => true,
+            _ => false
+        });
+        let options = NameParseOptions::default();
+        assert_eq!(Name::from_text_with_options(&labels_128, options).unwrap().label_count(), 128);
+    }
+
+    #[test]
+    fn idna_error_kinds() {
+        assert!(match Name::from_str("www.xn--99999999999.com") {
+            Err(NameParseError::InvalidPunycode(label)) => label == "xn--99999999999",
+            _ => false
+        });
+
+        assert!(match Name::from_str("a\u{378}b.com") {
+            Err(NameParseError::DisallowedCodepoint(name)) => name == "a\u{378}b.com",
+            _ => false
+        });
+
+        // Valid punycode decoding to an invalid label falls to the catch-all
+        assert!(match Name::from_str("xn--a.com") {
+            Err(NameParseError::IDNAError(_)) => true,
+            _ => false
+        });
+    }
+
+    #[test]
+    fn idna_error_kinds_not_disallowed() {
+        // The codepoints are allowed, the labels break the other IDNA rules
+        assert!(match Name::from_str("\u{301}a.com") {
+            Err(NameParseError::IDNAError(_)) => true,
+            _ => false
+        });
+        assert!(match Name::from_str("a\u{5d0}.com") {
+            Err(NameParseError::IDNAError(_)) => true,
+            _ => false
+        });
+
+        assert!(!is_disallowed_codepoint('\u{5d0}'));
+        assert!(!is_disallowed_codepoint('\u{301}'));
+        assert!(!is_disallowed_codepoint('a'));
+        assert!(is_disallowed_codepoint('\u{378}'));
+        assert!(is_disallowed_codepoint('\u{fffd}'));
+    }
+
+    #[test]
+    fn allowed_unicode_characters() {
+        assert!(Name::from_str("தமிழ்.wellsfargo.com").is_ok());
+    }
+
+    #[test]
+    fn is_empty_name() {
+        assert!(EMPTY.is_empty_name());
+        assert!(!ROOT.is_empty_name());
+        assert!(!Name::from_str("example.com").unwrap().is_empty_name());
+    }
+
+    #[test]
+    fn is_service_binding_owner() {
+        let owner = |name: &str| Name::from_str(name).unwrap().is_service_binding_owner();
+
+        assert!(owner("_443._https.example.com"));
+        assert!(owner("_8443._https.example.com."));
+        assert!(owner("_853._dns.example.net"));
+        assert!(owner("_dns.resolver.arpa"));
+
+        assert!(!owner("www.example.com"));
+        assert!(!owner("_dmarc.example.com"));
+        assert!(!owner("_443.example.com"));
+        assert!(!owner("_65536._https.example.com"));
+        assert!(!owner("_443._1https.example.com"));
+        assert!(!owner("_443._https"));
+        assert!(!owner("_dns"));
+    }
+}
+
+#[cfg(test)]
+mod tests_text {
+    use super::*;
+
+    #[test]
+    fn to_text() {
+        let name = Name::from_str("www.google.com.").unwrap();
+        assert_eq!(name.to_text(false), "www.google.com.");
+        assert_eq!(name.to_text(true), "www.google.com");
+
+        let name = Name::from_str("www.google.com").unwrap();
+        assert_eq!(name.to_text(false), "www.google.com");
+
+        assert_eq!(ROOT.to_text(false), ".");
+        assert_eq!(ROOT.to_text(true), ".");
+        assert_eq!(EMPTY.to_text(false), "");
+    }
+
+    #[test]
+    fn to_text_escaping() {
+        let name = unsafe { Name::from_bytes_raw(b"a b\\c\"d\x7f\x01.com.").unwrap() };
+        assert_eq!(name.to_text(false), "a\\032b\\\\c\\\"d\\127\\001.com.");
+    }
+
+    #[test]
+    fn from_masterfile_text() {
+        let name = Name::from_masterfile_text("a\\032b\\\\c\\\"d\\127\\001.com.").unwrap();
+        let expected = unsafe { Name::from_bytes_raw(b"a b\\c\"d\x7f\x01.com.").unwrap() };
+        assert!(name.case_matches(&expected));
+
+        let name = Name::from_masterfile_text("WWW.\\;\\(x\\).Example.com").unwrap();
+        assert_eq!(name.to_zonefile_text(), "WWW.\\;\\(x\\).Example.com");
+        assert_eq!(Name::from_masterfile_text(".").unwrap(), *ROOT);
+        assert_eq!(Name::from_masterfile_text("").unwrap(), *EMPTY);
+    }
+
+    #[test]
+    fn from_masterfile_text_invalid() {
+        assert!(match Name::from_masterfile_text("a\\.b.com") {
+            Err(NameParseError::InvalidLabelByte(b'.', 1)) => true,
+            _ => false
+        });
+        assert!(match Name::from_masterfile_text("a\\046b.com") {
+            Err(NameParseError::InvalidLabelByte(b'.', 1)) => true,
+            _ => false
+        });
+        assert!(match Name::from_masterfile_text("a.b\\200") {
+            Err(NameParseError::InvalidLabelByte(200, 3)) => true,
+            _ => false
+        });
+        assert!(match Name::from_masterfile_text("a.b\\256") {
+            Err(NameParseError::InvalidLabelByte(b'\\', 3)) => true,
+            _ => false
+        });
+        assert!(match Name::from_masterfile_text("a.b\\") {
+            Err(NameParseError::InvalidLabelByte(b'\\', 3)) => true,
+            _ => false
+        });
+        assert!(match Name::from_masterfile_text("தமிழ்.com") {
+            Err(NameParseError::NonAscii(0)) => true,
+            _ => false
+        });
+        assert!(match Name::from_masterfile_text("a..com") {
+            Err(NameParseError::EmptyLabel(1)) => true,
+            _ => false
+        });
+    }
+
+    #[test]
+    fn masterfile_text_round_trip() {
+        use rand::{Rng, SeedableRng};
+
+        // Labels of random ascii bytes other than '.', including the control bytes and
+        // the characters escaped in the presentation format
+        let mut rng = rand::rngs::StdRng::seed_from_u64(1035);
+        let mut label = || -> Vec<u8> {
+            let len = rng.gen_range(1..=12);
+            (0..len).map(|_| loop {
+                let byte = rng.gen_range(0x00..0x80u8);
+                if byte != b'.' {
+                    break byte;
+                }
+            }).collect()
+        };
+
+        for _ in 0..500 {
+            let labels = (0..5).map(|_| label()).collect::<Vec<Vec<u8>>>();
+            let count = 1 + labels[0][0] as usize % 5;
+            let name = Name::from_labels_iter(labels[..count].iter().map(|v| v.as_slice()),
+                                              labels[1][0] % 2 == 0).unwrap();
+
+            let text = name.to_text(false);
+            assert!(Name::from_masterfile_text(&text).unwrap().case_matches(&name), "{}", text);
+            let text = name.to_zonefile_text();
+            assert!(Name::from_masterfile_text(&text).unwrap().case_matches(&name), "{}", text);
+        }
+    }
+
+    #[test]
+    fn to_zonefile_text() {
+        let name = unsafe { Name::from_bytes_raw(b"a;b.com.").unwrap() };
+        assert_eq!(name.to_zonefile_text(), "a\\;b.com.");
+        assert_eq!(name.to_text(false), "a;b.com.");
+
+        let name = unsafe { Name::from_bytes_raw(b"a b.com.").unwrap() };
+        assert_eq!(name.to_zonefile_text(), "a\\032b.com.");
+
+        let name = unsafe { Name::from_bytes_raw(b"user@host.$(x).com").unwrap() };
+        assert_eq!(name.to_zonefile_text(), "user\\@host.\\$\\(x\\).com");
+        assert_eq!(name.to_text(false), "user@host.$(x).com");
+
+        assert_eq!(ROOT.to_zonefile_text(), ".");
+        assert_eq!(EMPTY.to_zonefile_text(), "@");
+    }
+
+    #[test]
+    fn write_text() {
+        let mut out = String::new();
+        for name in &["www.google.com.", "mail.google.com.", "google.com."] {
+            Name::from_str(name).unwrap().write_text(&mut out, true);
+            out.push(' ');
+        }
+        assert_eq!(out, "www.google.com mail.google.com google.com ");
+    }
+
+    #[test]
+    fn to_stable_string() {
+        let names = ["WWW.Example.com", "www.example.com.", "www.EXAMPLE.COM."];
+        for name in names.iter() {
+            let name = Name::from_ace(name).unwrap();
+            assert_eq!(name.to_stable_string(), "www.example.com.");
+        }
+
+        let name = unsafe { Name::from_bytes_raw(b"A B\\c.Com").unwrap() };
+        assert_eq!(name.to_stable_string(), "a\\032b\\\\c.com.");
+        assert_eq!(ROOT.to_stable_string(), ".");
+        assert_eq!(EMPTY.to_stable_string(), ".");
+    }
+
+    #[test]
+    fn write_presentation() {
+        // The last label is longer escaped than the stack buffer used for the escaping
+        let escaped_label = format!("a.{}.example", " ".repeat(63));
+        let names = [Name::from_str("www.google.com.").unwrap(), ROOT.clone(), EMPTY.clone(),
+            unsafe { Name::from_bytes_raw(b"a\\b\"c d\x7f.example.").unwrap() },
+            unsafe { Name::from_bytes_raw(escaped_label.as_bytes()).unwrap() }];
+
+        for name in names.iter() {
+            for omit_final_dot in &[false, true] {
+                let mut out = Vec::new();
+                name.write_presentation(&mut out, *omit_final_dot).unwrap();
+                assert_eq!(String::from_utf8(out).unwrap(), name.to_text(*omit_final_dot));
+            }
+        }
+
+        let mut out = Vec::new();
+        names[3].write_presentation(&mut out, false).unwrap();
+        assert_eq!(out, b"a\\\\b\\\"c\\032d\\127.example.".to_vec());
+
+        let mut out = Vec::new();
+        names[4].write_presentation(&mut out, false).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), format!("a.{}.example", "\\032".repeat(63)));
+    }
+}
+
+#[cfg(test)]
+mod tests_comparison {
+    use super::*;
+
+    #[test]
+    fn case_insensitive_equality() {
+        let lower = unsafe { Name::from_bytes_ascii(b"www.example.com").unwrap() };
+        let upper = unsafe { Name::from_bytes_ascii(b"WWW.Example.COM").unwrap() };
+        assert_eq!(lower, upper);
+        assert_eq!(lower.cmp(&upper), Ordering::Equal);
+
+        let other = unsafe { Name::from_bytes_ascii(b"www.example.org").unwrap() };
+        assert_ne!(lower, other);
+    }
+
+    #[test]
+    fn equality_length_short_circuit() {
+        // Trailing dot is honored, so absolute and relative forms are not equal
+        assert_ne!(Name::from_str("a.com").unwrap(), Name::from_str("a.com.").unwrap());
+        assert_ne!(Name::from_str("a.com.").unwrap(), Name::from_str("a.com").unwrap());
+
+        assert_eq!(Name::from_str("a.com.").unwrap(), Name::from_str("A.COM.").unwrap());
+        assert_ne!(Name::from_str("a.com").unwrap(), Name::from_str("ab.com").unwrap());
+    }
+
+    #[test]
+    fn eq_ignore_case() {
+        let lower = unsafe { Name::from_bytes_ascii(b"www.example.com").unwrap() };
+        let upper = unsafe { Name::from_bytes_ascii(b"WWW.Example.COM").unwrap() };
+        assert!(lower.eq_ignore_case(&upper));
+        assert!(!lower.case_matches(&upper));
+
+        let other = unsafe { Name::from_bytes_ascii(b"www.example.org").unwrap() };
+        assert!(!lower.eq_ignore_case(&other));
+
+        let relative = Name::from_str("a.com").unwrap();
+        let absolute = Name::from_str("A.COM.").unwrap();
+        assert!(!relative.eq_ignore_case(&absolute));
+        assert!(!absolute.eq_ignore_case(&relative));
+        assert!(absolute.eq_ignore_case(&Name::from_str("a.com.").unwrap()));
+        assert!(!relative.eq_ignore_case(&Name::from_str("ab.com").unwrap()));
+
+        assert!(ROOT.eq_ignore_case(&ROOT));
+        assert!(!ROOT.eq_ignore_case(&EMPTY));
+    }
+
+    fn names(names: &[&str]) -> Vec<Name> {
+        names.iter().map(|v| Name::from_str(v).unwrap()).collect()
+    }
+
+    #[test]
+    fn canonical_order() {
+        // Example from RFC 4034 section 6.1
+        let expected: Vec<&[u8]> = vec![
+            b"example.", b"a.example.", b"yljkjljk.a.example.", b"Z.a.example.",
+            b"zABC.a.EXAMPLE.", b"z.example.", b"\x01.z.example.", b"*.z.example.",
+            b"\xc8.z.example."];
+
+        let mut sorted = expected.iter().rev()
+            .map(|v| unsafe { Name::from_bytes_raw(v).unwrap() })
+            .collect::<Vec<Name>>();
+        sorted.sort_by(|a, b| a.canonical_cmp(b));
+
+        assert_eq!(sorted.iter().map(|v| v.value.as_slice()).collect::<Vec<&[u8]>>(), expected);
+    }
+
+    #[test]
+    fn canonical_order_absolute_relative() {
+        let relative = Name::from_str("example.com").unwrap();
+        let absolute = Name::from_str("example.com.").unwrap();
+        assert_eq!(relative.canonical_cmp(&absolute), Ordering::Less);
+        assert_eq!(absolute.canonical_cmp(&absolute), Ordering::Equal);
+    }
+
+    #[test]
+    fn ord_absolute_relative_adjacent() {
+        let mut sorted = names(&["example.com-x", "example.com.", "example.com", "example.co",
+            ".", ""]);
+        sorted.sort();
+        assert_eq!(sorted.iter().map(|v| v.to_string()).collect::<Vec<String>>(),
+                   vec!["", ".", "example.co", "example.com", "example.com.", "example.com-x"]);
+
+        let relative = Name::from_str("example.com").unwrap();
+        let absolute = Name::from_str("example.com.").unwrap();
+        assert_eq!(relative.cmp(&absolute), Ordering::Less);
+        assert_eq!(absolute.cmp(&relative), Ordering::Greater);
+        assert_eq!(absolute.cmp(&Name::from_ace("EXAMPLE.com.").unwrap()), Ordering::Equal);
+    }
+
+    #[test]
+    fn common_prefix_len() {
+        let name = Name::from_str("a.b.com").unwrap();
+        assert_eq!(name.common_prefix_len(&Name::from_str("a.b.com").unwrap()), 3);
+        assert_eq!(name.common_prefix_len(&Name::from_str("A.B.COM.").unwrap()), 3);
+        assert_eq!(name.common_prefix_len(&Name::from_str("a.b.org").unwrap()), 2);
+        assert_eq!(name.common_prefix_len(&Name::from_str("a.c.com").unwrap()), 1);
+        assert_eq!(name.common_prefix_len(&Name::from_str("b.com").unwrap()), 0);
+        assert_eq!(name.common_prefix_len(&ROOT), 0);
+    }
+
+    #[test]
+    fn common_suffix_len() {
+        let name = Name::from_str("a.example.com").unwrap();
+        assert_eq!(name.common_suffix_len(&Name::from_str("A.Example.COM.").unwrap()), 3);
+        assert_eq!(name.common_suffix_len(&Name::from_str("b.example.com").unwrap()), 2);
+        assert_eq!(name.common_suffix_len(&Name::from_str("example.org").unwrap()), 0);
+        assert_eq!(name.common_suffix_len(&ROOT), 0);
+    }
+
+    #[test]
+    fn shared_suffix_at_least() {
+        let name = Name::from_str("a.b.example.com").unwrap();
+        let other = Name::from_ace("x.Example.COM.").unwrap();
+
+        assert!(name.shared_suffix_at_least(&other, 0));
+        assert!(name.shared_suffix_at_least(&other, 2));
+        assert!(!name.shared_suffix_at_least(&other, 3));
+        assert!(!name.shared_suffix_at_least(&Name::from_str("example.org").unwrap(), 1));
+        assert!(!name.shared_suffix_at_least(&Name::from_str("com").unwrap(), 2));
+        assert!(ROOT.shared_suffix_at_least(&EMPTY, 0));
+        assert!(!ROOT.shared_suffix_at_least(&ROOT, 1));
+    }
+
+    #[test]
+    fn shared_suffix_at_least_trailing_labels() {
+        let name = Name::from_str("a.b.c.d.example.com").unwrap();
+
+        // Only the trailing `min` labels decide the result
+        assert!(name.shared_suffix_at_least(&Name::from_str("x.y.c.d.example.com").unwrap(), 4));
+        assert!(!name.shared_suffix_at_least(&Name::from_str("x.y.c.d.example.com").unwrap(), 5));
+
+        // A differing TLD fails the check regardless of the labels before it
+        let other = Name::from_str("a.b.c.d.example.org").unwrap();
+        assert!(!name.shared_suffix_at_least(&other, 1));
+        assert!(!name.shared_suffix_at_least(&other, 6));
+        assert_eq!(name.common_suffix_len(&other), 0);
+    }
+
+    #[test]
+    fn symmetric_label_diff() {
+        let name = Name::from_str("a.b.example.com").unwrap();
+        let other = Name::from_str("c.example.com").unwrap();
+        let to_strings = |labels: Vec<Label>| labels.iter().map(|v| v.to_string())
+            .collect::<Vec<String>>();
+
+        let (ours, theirs) = name.symmetric_label_diff(&other);
+        assert_eq!(to_strings(ours), vec!["a", "b"]);
+        assert_eq!(to_strings(theirs), vec!["c"]);
+
+        let (ours, theirs) = name.symmetric_label_diff(&name);
+        assert!(ours.is_empty() && theirs.is_empty());
+    }
+
+    #[test]
+    fn cmp_by_depth() {
+        let mut sorted = names(&["b.example.org.", "com.", "a.example.com.", "example.org.",
+            "example.com", "example.com.", "org.", "."]);
+        sorted.sort_by(|a, b| a.cmp_by_depth(b));
+        assert_eq!(sorted.iter().map(|v| v.to_string()).collect::<Vec<String>>(),
+                   vec![".", "com.", "org.", "example.com", "example.com.", "example.org.",
+                        "a.example.com.", "b.example.org."]);
+    }
+
+    #[test]
+    fn nsec_covers() {
+        let name = |v: &str| Name::from_str(v).unwrap();
+        let (owner, next) = (name("b.example."), name("d.example."));
+
+        // Inside the interval
+        assert!(Name::nsec_covers(&owner, &next, &name("c.example.")));
+        assert!(Name::nsec_covers(&owner, &next, &name("a.b.example.")));
+        assert!(Name::nsec_covers(&owner, &next, &name("C.EXAMPLE.")));
+
+        // Outside the interval, including its ends
+        assert!(!Name::nsec_covers(&owner, &next, &name("b.example.")));
+        assert!(!Name::nsec_covers(&owner, &next, &name("d.example.")));
+        assert!(!Name::nsec_covers(&owner, &next, &name("a.example.")));
+        assert!(!Name::nsec_covers(&owner, &next, &name("a.d.example.")));
+        assert!(!Name::nsec_covers(&owner, &next, &name("example.")));
+    }
+
+    #[test]
+    fn nsec_covers_wrap_around() {
+        let name = |v: &str| Name::from_str(v).unwrap();
+        // The last NSEC record of the zone, pointing back to the apex
+        let (owner, apex) = (name("z.example."), name("example."));
+
+        assert!(Name::nsec_covers(&owner, &apex, &name("zz.example.")));
+        assert!(Name::nsec_covers(&owner, &apex, &name("a.z.example.")));
+        assert!(!Name::nsec_covers(&owner, &apex, &name("z.example.")));
+        assert!(!Name::nsec_covers(&owner, &apex, &name("example.")));
+        assert!(!Name::nsec_covers(&owner, &apex, &name("a.example.")));
+
+        // A zone with only the apex
+        assert!(Name::nsec_covers(&apex, &apex, &name("a.example.")));
+        assert!(!Name::nsec_covers(&apex, &apex, &name("example.")));
+    }
+
+    #[test]
+    fn ordering_matches_canonical() {
+        assert!(Name::ordering_matches_canonical(
+            &names(&["a.com.", "b.a.com.", "b.com."])));
+
+        // Byte order puts a.org before b.com while canonical order puts com before org
+        assert!(!Name::ordering_matches_canonical(&names(&["a.org.", "b.com."])));
+
+        // Byte order puts a-b.com before a.com while canonical order puts a.com first
+        assert!(!Name::ordering_matches_canonical(&names(&["a.com.", "a-b.com."])));
+    }
+
+    #[test]
+    fn randomize_case() {
+        let name = Name::from_str("www.a-very-long-label-for-randomization.example.com.").unwrap();
+        let mut rng = rand::thread_rng();
+
+        let randomized = name.randomize_case(&mut rng);
+        assert_eq!(randomized, name);
+        assert!(randomized.is_absolute());
+
+        // All the 40 letters retaining the case has a probability of 2^-40
+        assert!((0..4).map(|_| name.randomize_case(&mut rng))
+            .any(|v| v.value != name.value));
+    }
+
+    #[test]
+    fn case_matches() {
+        let query = Name::from_ace("wWw.ExAmple.cOm.").unwrap();
+
+        assert!(query.case_matches(&Name::from_ace("wWw.ExAmple.cOm.").unwrap()));
+
+        let response = Name::from_ace("www.example.com.").unwrap();
+        assert!(!query.case_matches(&response));
+        assert_eq!(query, response);
+
+        assert!(!query.case_matches(&Name::from_ace("wWw.ExAmple.cOm").unwrap()));
+    }
+
+    #[test]
+    fn eq_unicode() {
+        let stored = Name::from_ace("XN--rlcus7b3d.wellsfargo.com").unwrap();
+        assert!(stored.eq_unicode("தமிழ்.wellsfargo.com"));
+        assert!(stored.eq_unicode("தமிழ்.WellsFargo.com"));
+
+        assert!(!stored.eq_unicode("தமிழ்.wellsfargo.com."));
+        assert!(!stored.eq_unicode("தமிழ.wellsfargo.com"));
+        assert!(!stored.eq_unicode("secure\u{2488}wellsfargo.com"));
+    }
+
+    #[test]
+    fn label_edit_distance() {
+        let name = Name::from_str("example.com").unwrap();
+
+        assert_eq!(name.label_edit_distance(&Name::from_str("example.com.").unwrap()), 0);
+        assert_eq!(name.label_edit_distance(&Name::from_ace("EXAMPLE.com").unwrap()), 0);
+        assert_eq!(name.label_edit_distance(&Name::from_str("examp1e.com").unwrap()), 1);
+        assert_eq!(name.label_edit_distance(&Name::from_str("exampe.com").unwrap()), 1);
+        assert_eq!(name.label_edit_distance(&Name::from_str("exmaple.com").unwrap()), 2);
+        assert_eq!(name.label_edit_distance(&Name::from_str("wellsfargo.org").unwrap()), 12);
+
+        // Counted on the unicode form, the punycode forms differ much more
+        let unicode = Name::from_str("exämple.com").unwrap();
+        assert_eq!(name.label_edit_distance(&unicode), 1);
+    }
+
+    #[test]
+    fn canonical_cow() {
+        let lower = Name::from_str("www.example.com.").unwrap();
+        assert!(match lower.canonical_cow() {
+            Cow::Borrowed(name) => std::ptr::eq(name, &lower),
+            Cow::Owned(_) => false
+        });
+
+        let mixed = Name::from_ace("www.Example.COM.").unwrap();
+        assert!(match mixed.canonical_cow() {
+            Cow::Owned(name) => name.case_matches(&lower),
+            Cow::Borrowed(_) => false
+        });
+    }
+
+    #[test]
+    fn canonical_str() {
+        let lower = Name::from_str("www.example.com.").unwrap();
+        assert!(match lower.canonical_str() {
+            Cow::Borrowed(text) => text == "www.example.com.",
+            Cow::Owned(_) => false
+        });
+
+        let mixed = Name::from_ace("www.Example.COM.").unwrap();
+        assert!(match mixed.canonical_str() {
+            Cow::Owned(text) => text == "www.example.com.",
+            Cow::Borrowed(_) => false
+        });
+    }
+
+    /// A hasher collecting the fed bytes, to check what gets hashed
+    #[derive(Default)]
+    struct CollectingHasher(Vec<u8>);
+
+    impl Hasher for CollectingHasher {
+        fn finish(&self) -> u64 {
+            self.0.iter().fold(0, |acc, v| acc.wrapping_mul(31).wrapping_add(*v as u64))
+        }
+
+        fn write(&mut self, bytes: &[u8]) {
+            self.0.extend_from_slice(bytes)
+        }
+    }
+
+    #[test]
+    fn case_insensitive_hash() {
+        let lower = unsafe { Name::from_bytes_ascii(b"www.example.com").unwrap() };
+        let upper = unsafe { Name::from_bytes_ascii(b"WWW.Example.COM").unwrap() };
+
+        let mut lower_hasher = CollectingHasher::default();
+        lower.hash_canonical(&mut lower_hasher);
+        let mut upper_hasher = CollectingHasher::default();
+        upper.hash_canonical(&mut upper_hasher);
+        assert_eq!(lower_hasher.0, upper_hasher.0);
+        assert_eq!(lower_hasher.finish(), upper_hasher.finish());
+
+        // The Hash impl delegates to hash_canonical
+        let mut hasher = CollectingHasher::default();
+        upper.hash(&mut hasher);
+        assert_eq!(hasher.0, lower_hasher.0);
+
+        let mut set = std::collections::HashSet::new();
+        set.insert(lower);
+        assert!(set.contains(&upper));
+    }
+
+    #[test]
+    fn canonical_hash_u64() {
+        let lower = Name::from_str("www.example.com").unwrap();
+        let upper = Name::from_ace("WWW.Example.COM").unwrap();
+
+        assert_eq!(lower.canonical_hash_u64(), upper.canonical_hash_u64());
+        assert_eq!(lower.canonical_hash_u64(), lower.canonical_hash_u64());
+        assert_ne!(lower.canonical_hash_u64(),
+                   Name::from_str("www.example.com.").unwrap().canonical_hash_u64());
+
+        // The fixed FNV-1a values
+        assert_eq!(EMPTY.canonical_hash_u64(), 0xcbf29ce484222325);
+        assert_eq!(Name::from_ace("A").unwrap().canonical_hash_u64(), 0xaf63dc4c8601ec8c);
+    }
+}
+
+#[cfg(test)]
+mod tests_wire {
+    use super::*;
+
+    #[test]
+    fn to_wire() {
+        let mut out = Vec::new();
+        Name::from_str("www.google.com.").unwrap().to_wire(&mut out).unwrap();
+        assert_eq!(out, b"\x03www\x06google\x03com\x00");
+    }
+
+    #[test]
+    fn wire_len() {
+        for name in &["www.google.com.", ".", "a.", "example.com"] {
+            let name = Name::from_str(name).unwrap();
+            let mut out = Vec::new();
+            name.to_wire_opaque(&mut out);
+            assert_eq!(name.wire_len(), out.len());
+        }
+
+        let mut out = vec![0xff];
+        Name::from_str("www.google.com.").unwrap().to_wire(&mut out).unwrap();
+        assert_eq!(Name::from_str("www.google.com.").unwrap().wire_len(), out.len() - 1);
+        assert_eq!(EMPTY.wire_len(), 1);
+    }
+
+    #[test]
+    fn to_wire_opaque() {
+        let name = unsafe { Name::from_bytes_ascii(b"WWW.Example.com.").unwrap() };
+
+        let mut opaque = Vec::new();
+        name.to_wire_opaque(&mut opaque);
+        assert_eq!(opaque, b"\x03WWW\x07Example\x03com\x00");
+
+        let mut canonical = Vec::new();
+        name.canonical_cow().to_wire(&mut canonical).unwrap();
+        assert_eq!(canonical, b"\x03www\x07example\x03com\x00");
+        assert_ne!(opaque, canonical);
+
+        let mut out = Vec::new();
+        EMPTY.to_wire_opaque(&mut out);
+        assert_eq!(out, b"\x00");
+    }
+
+    #[test]
+    fn to_wire_compressed() {
+        let mut out = Vec::new();
+        let mut compression = HashMap::new();
+        let policy = CompressionPolicy::Allowed;
+
+        Name::from_str("www.example.com.").unwrap()
+            .to_wire_compressed(&mut out, &mut compression, policy).unwrap();
+        Name::from_ace("mail.EXAMPLE.com.").unwrap()
+            .to_wire_compressed(&mut out, &mut compression, policy).unwrap();
+        Name::from_str("example.com.").unwrap()
+            .to_wire_compressed(&mut out, &mut compression, policy).unwrap();
+        ROOT.to_wire_compressed(&mut out, &mut compression, policy).unwrap();
+        assert_eq!(out, b"\x03www\x07example\x03com\x00\x04mail\xc0\x04\xc0\x04\x00".to_vec());
+
+        let (name, _) = Name::from_wire(&out, 17).unwrap();
+        assert_eq!(name.to_string(), "mail.example.com.");
+        assert_eq!(compression.len(), 4);
+
+        assert!(match EMPTY.to_wire_compressed(&mut out, &mut compression, policy) {
+            Err(NameParseError::EmptyName) => true,
+            _ => false
+        });
+    }
+
+    #[test]
+    fn canonical_wire() {
+        for name in &["WWW.Example.COM.", "www.example.com.", "_Sip._TCP.a-B.org.", "."] {
+            let name = unsafe { Name::from_bytes_ascii(name.as_bytes()).unwrap() };
+
+            let mut expected = Vec::new();
+            name.canonical_cow().to_wire(&mut expected).unwrap();
+            assert_eq!(name.canonical_wire().unwrap(), expected);
+        }
+
+        let name = unsafe { Name::from_bytes_ascii(b"WWW.Example.COM.").unwrap() };
+        assert_eq!(name.canonical_wire().unwrap(), b"\x03www\x07example\x03com\x00");
+
+        assert!(match Name::from_str("example.com").unwrap().canonical_wire() {
+            Err(NameParseError::RelativeName(name)) => name == "example.com",
+            _ => false
+        });
+        assert!(match EMPTY.canonical_wire() {
+            Err(NameParseError::EmptyName) => true,
+            _ => false
+        });
+    }
+
+    #[test]
+    fn compressed_wire_len() {
+        let mut out = Vec::new();
+        let mut compression = HashMap::new();
+        let policy = CompressionPolicy::Allowed;
+
+        Name::from_str("www.example.com.").unwrap()
+            .to_wire_compressed(&mut out, &mut compression, policy).unwrap();
+
+        for name in &["mail.example.com.", "example.com.", "www.example.com.", "a.b.org.", "."] {
+            let name = Name::from_str(name).unwrap();
+            let estimated = name.compressed_wire_len(&compression);
+
+            let start = out.len();
+            name.to_wire_compressed(&mut out, &mut compression, policy).unwrap();
+            assert_eq!(estimated, out.len() - start);
+        }
+
+        let www = Name::from_str("www.example.com.").unwrap();
+        let mut offsets = HashMap::new();
+        assert_eq!(www.compressed_wire_len(&offsets), 17);
+        offsets.insert(Name::from_str("example.com.").unwrap(), MAX_COMPRESSION_OFFSET as u16 + 1);
+        assert_eq!(www.compressed_wire_len(&offsets), 17);
+        offsets.insert(Name::from_str("com.").unwrap(), 12);
+        assert_eq!(www.compressed_wire_len(&offsets), 14);
+    }
+
+    #[test]
+    fn to_wire_compressed_offset_boundary() {
+        let policy = CompressionPolicy::Allowed;
+        let mut out = vec![0u8; MAX_COMPRESSION_OFFSET - 3];
+        let mut compression = HashMap::new();
+
+        // www at 0x3ffc and example at 0x4000, beyond the pointers' range
+        Name::from_str("www.example.com.").unwrap()
+            .to_wire_compressed(&mut out, &mut compression, policy).unwrap();
+        assert_eq!(compression.len(), 1);
+        assert_eq!(compression[&Name::from_str("www.example.com.").unwrap()], 0x3ffc);
+
+        assert!(match Name::from_str("mail.example.com.").unwrap()
+            .to_wire_compressed(&mut out, &mut compression, policy) {
+            Err(NameParseError::OffsetTooLarge(offset)) => offset == 0x3ffc + 17,
+            _ => false
+        });
+
+        Name::from_str("mail.example.com.").unwrap()
+            .to_wire_compressed(&mut out, &mut compression, CompressionPolicy::Forbidden)
+            .unwrap();
+        assert_eq!(out.len(), 0x3ffc + 17 + 18);
+    }
+
+    #[test]
+    fn to_wire_compressed_no_pointer_beyond_range() {
+        let mut out = Vec::new();
+        let mut compression = HashMap::new();
+        compression.insert(Name::from_str("example.com.").unwrap(), 0x4000);
+
+        Name::from_str("www.example.com.").unwrap()
+            .to_wire_compressed(&mut out, &mut compression, CompressionPolicy::Allowed).unwrap();
+        assert_eq!(out, b"\x03www\x07example\x03com\x00");
+    }
+
+    #[test]
+    fn to_wire_compression_forbidden() {
+        let mut out = Vec::new();
+        let mut compression = HashMap::new();
+
+        Name::from_str("www.example.com.").unwrap()
+            .to_wire_compressed(&mut out, &mut compression, CompressionPolicy::Allowed).unwrap();
+        Name::from_str("mail.example.com.").unwrap()
+            .to_wire_compressed(&mut out, &mut compression, CompressionPolicy::Forbidden)
+            .unwrap();
+        assert_eq!(&out[17..], b"\x04mail\x07example\x03com\x00");
+        assert_eq!(compression.len(), 3);
+
+        assert!(match Name::from_str("example.com").unwrap()
+            .to_wire_compressed(&mut out, &mut compression, CompressionPolicy::Forbidden) {
+            Err(NameParseError::RelativeName(_)) => true,
+            _ => false
+        });
+    }
+
+    #[test]
+    fn to_wire_too_large_after_concatenation() {
+        // 127 characters each, 255 together, which is 256 bytes in the wire format
+        let host = Name::from_str(&format!("{}.{}", "x".repeat(63), "x".repeat(63))).unwrap();
+        let origin = Name::from_str(&format!("{}.{}.", "y".repeat(63), "y".repeat(62))).unwrap();
+        assert!(host.concat_str("com.").unwrap().to_wire(&mut Vec::new()).is_ok());
+        origin.to_wire(&mut Vec::new()).unwrap();
+
+        let name = host.concatenate(&origin).unwrap();
+        assert_eq!(name.wire_len(), 256);
+
+        let mut out = Vec::new();
+        assert!(match name.to_wire(&mut out) {
+            Err(NameParseError::NameTooLarge(_)) => true,
+            _ => false
+        });
+        assert!(out.is_empty());
+
+        let mut buf = [0u8; 512];
+        assert!(match name.write_wire(&mut buf) {
+            Err(NameParseError::NameTooLarge(_)) => true,
+            _ => false
+        });
+        assert!(match name.to_wire_compressed(&mut out, &mut HashMap::new(),
+                                              CompressionPolicy::Allowed) {
+            Err(NameParseError::NameTooLarge(_)) => true,
+            _ => false
+        });
+
+        // One character less fits exactly
+        let origin = Name::from_str(&format!("{}.{}.", "y".repeat(63), "y".repeat(61))).unwrap();
+        let name = host.concatenate(&origin).unwrap();
+        name.to_wire(&mut out).unwrap();
+        assert_eq!(out.len(), 255);
+    }
+
+    #[test]
+    fn root_to_wire() {
+        let mut out = Vec::new();
+        ROOT.to_wire(&mut out).unwrap();
+        assert_eq!(out, b"\x00");
+
+        let mut out = [0xffu8; 1];
+        assert_eq!(ROOT.write_wire(&mut out).unwrap(), 1);
+        assert_eq!(out, [0u8]);
+
+        let mut out = [0xffu8; 0];
+        assert!(match ROOT.write_wire(&mut out) {
+            Err(NameParseError::BufferTooSmall(0, 1)) => true,
+            _ => false
+        });
+    }
+
+    #[test]
+    fn empty_to_wire() {
+        let mut out = Vec::new();
+        assert!(match EMPTY.to_wire(&mut out) {
+            Err(NameParseError::EmptyName) => true,
+            _ => false
+        });
+        assert!(out.is_empty());
+
+        let mut out = [0xffu8; 4];
+        assert!(match EMPTY.write_wire(&mut out) {
+            Err(NameParseError::EmptyName) => true,
+            _ => false
+        });
+    }
+
+    #[test]
+    fn relative_to_wire() {
+        let mut out = Vec::new();
+        assert!(match Name::from_str("example.com").unwrap().to_wire(&mut out) {
+            Err(NameParseError::RelativeName(name)) => name == "example.com",
+            _ => false
+        });
+        assert!(out.is_empty());
+
+        Name::from_str("example.com.").unwrap().to_wire(&mut out).unwrap();
+        assert_eq!(out, b"\x07example\x03com\x00");
+
+        let mut out = [0xffu8; 16];
+        assert!(match Name::from_str("example.com").unwrap().write_wire(&mut out) {
+            Err(NameParseError::RelativeName(_)) => true,
+            _ => false
+        });
+        assert_eq!(Name::from_str("example.com.").unwrap().write_wire(&mut out).unwrap(), 13);
+
+        assert!(!Name::from_str("example.com").unwrap().fits_in(512));
+        assert!(!Name::from_str("example.com").unwrap().eq_wire(b"\x07example\x03com"));
+    }
+
+    #[test]
+    fn corpus_seeds() {
+        for seed in Name::corpus_seeds() {
+            for offset in 0..=seed.len() {
+                let _ = Name::from_wire(&seed, offset);
+                let _ = Name::from_wire_no_compression(&seed, offset);
+            }
+        }
+
+        let seeds = Name::corpus_seeds();
+        assert_eq!(Name::from_wire(&seeds[2], 0).unwrap().0.labels().len(), 128);
+        assert_eq!(Name::from_wire(&seeds[3], 13).unwrap().0.to_string(), "www.example.com.");
+        assert!(match Name::from_wire(&seeds[4], 0) {
+            Err(NameParseError::TooManyPointers(_)) => true,
+            _ => false
+        });
+    }
+
+    #[test]
+    fn from_wire_with_limit() {
+        // The root name at 0 and a chain of 3 pointers starting at 5
+        let buf = b"\x00\xc0\x00\xc0\x01\xc0\x03";
+
+        assert_eq!(Name::from_wire_with_limit(buf, 5, 3).unwrap(), (ROOT.clone(), 7));
+        assert_eq!(Name::from_wire_with_limit(buf, 3, 2).unwrap(), (ROOT.clone(), 5));
+        assert!(match Name::from_wire_with_limit(buf, 5, 2) {
+            Err(NameParseError::TooManyPointers(2)) => true,
+            _ => false
+        });
+
+        assert_eq!(Name::from_wire_with_limit(buf, 0, 0).unwrap(), (ROOT.clone(), 1));
+        assert!(match Name::from_wire_with_limit(buf, 1, 0) {
+            Err(NameParseError::TooManyPointers(0)) => true,
+            _ => false
+        });
+    }
+
+    #[test]
+    fn root_from_wire() {
+        let (name, offset) = Name::from_wire(b"\x00", 0).unwrap();
+        assert_eq!(name, *ROOT);
+        assert!(name.is_absolute());
+        assert_eq!(offset, 1);
+
+        let (name, offset) = Name::from_wire(b"\x03com\x00\x00\x01", 5).unwrap();
+        assert_eq!(name, *ROOT);
+        assert_eq!(offset, 6);
+    }
+
+    #[test]
+    fn from_wire() {
+        let buf = b"\x03www\x06google\x03com\x00\x04mail\xc0\x04";
+
+        let (name, offset) = Name::from_wire(buf, 0).unwrap();
+        assert_eq!(name.to_string(), "www.google.com.");
+        assert_eq!(offset, 16);
+
+        let (name, offset) = Name::from_wire(buf, 16).unwrap();
+        assert_eq!(name.to_string(), "mail.google.com.");
+        assert_eq!(offset, 23);
+    }
+
+    #[test]
+    fn iter_wire_labels() {
+        let buf = b"\x03www\x06google\x03com\x00\x04mail\xc0\x04";
+
+        let labels = super::iter_wire_labels(buf, 16).collect::<Result<Vec<&[u8]>, _>>().unwrap();
+        assert_eq!(labels, vec![&b"mail"[..], b"google", b"com"]);
+
+        let tld = super::iter_wire_labels(buf, 16).last().unwrap().unwrap();
+        assert_eq!(tld, b"com");
+        assert_eq!(super::iter_wire_labels(b"\x00", 0).count(), 0);
+    }
+
+    #[test]
+    fn iter_wire_labels_invalid() {
+        let mut labels = super::iter_wire_labels(b"\x03www\x06goo", 0);
+        assert_eq!(labels.next().unwrap().unwrap(), b"www");
+        assert!(match labels.next() {
+            Some(Err(NameParseError::UnexpectedEnd(8))) => true,
+            _ => false
+        });
+        assert!(labels.next().is_none());
+
+        let mut labels = super::iter_wire_labels(b"\x01a\xc0\x00", 0);
+        assert_eq!(labels.by_ref().take_while(|v| v.is_ok()).count(), MAX_POINTERS + 1);
+        assert!(labels.next().is_none());
+
+        assert!(match super::iter_wire_labels(b"\x40", 0).next() {
+            Some(Err(NameParseError::InvalidLabelType(0x40, 0))) => true,
+            _ => false
+        });
+    }
+
+    #[test]
+    fn from_wire_invalid() {
+        assert!(match Name::from_wire(b"\x03www\x06goo", 0) {
+            Err(NameParseError::UnexpectedEnd(_)) => true,
+            _ => false
+        });
+
+        assert!(match Name::from_wire(b"\x03www", 0) {
+            Err(NameParseError::UnexpectedEnd(4)) => true,
+            _ => false
+        });
+
+        assert!(match Name::from_wire(b"\x03w.w\x00", 0) {
+            Err(NameParseError::InvalidLabelByte(b'.', 2)) => true,
+            _ => false
+        });
+
+        assert!(match Name::from_wire(b"\x43www\x00", 0) {
+            Err(NameParseError::InvalidLabelType(0x43, 0)) => true,
+            _ => false
+        });
+
+        assert!(match Name::from_wire(b"\x03www\xc0\x00", 0) {
+            Err(NameParseError::TooManyPointers(MAX_POINTERS)) => true,
+            _ => false
+        });
+
+        let mut long_name = std::iter::repeat(b"\x01x".to_vec()).take(128).flatten()
+            .collect::<Vec<u8>>();
+        long_name.push(0);
+        assert!(match Name::from_wire(&long_name, 0) {
+            Err(NameParseError::NameTooLarge(_)) => true,
+            _ => false
+        });
+    }
+
+    #[test]
+    fn eq_wire() {
+        let name = Name::from_str("example.com.").unwrap();
+        assert!(name.eq_wire(b"\x07example\x03com\x00"));
+        assert!(name.eq_wire(b"\x07EXAMPLE\x03Com\x00"));
+
+        assert!(!name.eq_wire(b"\x07example\x03org\x00"));
+        assert!(!name.eq_wire(b"\x07example\x03com"));
+        assert!(!name.eq_wire(b"\x07example\x03com\x00\x00"));
+        assert!(!name.eq_wire(b"\x07example\x04com\x00"));
+        assert!(!name.eq_wire(b"\x07example\xc0\x00"));
+        assert!(!name.eq_wire(b""));
+
+        assert!(ROOT.eq_wire(b"\x00"));
+        assert!(!EMPTY.eq_wire(b""));
+    }
+
+    #[test]
+    fn to_wire_tracked() {
+        let mut out = vec![0xffu8; 12];
+        let mut offsets = Vec::new();
+
+        Name::from_str("google.com.").unwrap()
+            .to_wire_tracked(&mut out, |v| offsets.push(v)).unwrap();
+        Name::from_str("www.google.com.").unwrap()
+            .to_wire_tracked(&mut out, |v| offsets.push(v)).unwrap();
+        assert!(EMPTY.to_wire_tracked(&mut out, |v| offsets.push(v)).is_err());
+
+        assert_eq!(offsets, vec![12, 24]);
+        assert_eq!(&out[12..24], b"\x06google\x03com\x00");
+        assert_eq!(&out[24..], b"\x03www\x06google\x03com\x00");
+    }
+
+    #[test]
+    fn from_wire_no_compression() {
+        let buf = b"\x03www\x06google\x03com\x00\x04mail\xc0\x04";
+
+        let (name, offset) = Name::from_wire_no_compression(buf, 0).unwrap();
+        assert_eq!(name.to_string(), "www.google.com.");
+        assert_eq!(offset, 16);
+
+        assert!(match Name::from_wire_no_compression(buf, 16) {
+            Err(NameParseError::CompressionNotAllowed(21)) => true,
+            _ => false
+        });
+    }
+
+    #[test]
+    fn from_wire_exact() {
+        let name = Name::from_wire_exact(b"\x03www\x06google\x03com\x00").unwrap();
+        assert_eq!(name.to_string(), "www.google.com.");
+        assert_eq!(Name::from_wire_exact(b"\x00").unwrap(), *ROOT);
+
+        assert!(match Name::from_wire_exact(b"\x03www\x06google\x03com\x00\x00\x01") {
+            Err(NameParseError::TrailingData(2, 16)) => true,
+            _ => false
+        });
+        assert!(match Name::from_wire_exact(b"\x03www\x06google\x03com") {
+            Err(NameParseError::UnexpectedEnd(_)) => true,
+            _ => false
+        });
+        assert!(match Name::from_wire_exact(b"\x03www\xc0\x00") {
+            Err(NameParseError::CompressionNotAllowed(4)) => true,
+            _ => false
+        });
+    }
+
+    #[test]
+    fn fits_in() {
+        // 16 bytes in wire format
+        let name = Name::from_str("www.google.com.").unwrap();
+        assert!(name.fits_in(17));
+        assert!(name.fits_in(16));
+        assert!(!name.fits_in(15));
+        assert!(!name.fits_in(0));
+
+        assert!(ROOT.fits_in(1));
+        assert!(!ROOT.fits_in(0));
+        assert!(!EMPTY.fits_in(512));
+    }
+
+    #[test]
+    fn write_wire_exact_fit() {
+        let name = Name::from_str("www.google.com.").unwrap();
+        let mut out = [0xffu8; 16];
+        assert_eq!(name.write_wire(&mut out).unwrap(), 16);
+        assert_eq!(&out[..], b"\x03www\x06google\x03com\x00");
+    }
+
+    #[test]
+    fn write_wire_larger_buffer() {
+        let name = Name::from_str("google.com.").unwrap();
+        let mut out = [0xffu8; 16];
+        assert_eq!(name.write_wire(&mut out).unwrap(), 12);
+        assert_eq!(&out[..12], b"\x06google\x03com\x00");
+        assert_eq!(out[12], 0xff);
+    }
+
+    #[test]
+    fn write_wire_too_small() {
+        let name = Name::from_str("www.google.com.").unwrap();
+        let mut out = [0xffu8; 15];
+        assert!(match name.write_wire(&mut out) {
+            Err(NameParseError::BufferTooSmall(available, required)) =>
+                available == 15 && required == 16,
+            _ => false
+        });
+        assert_eq!(out, [0xffu8; 15]);
+    }
+}
+
+#[cfg(all(test, feature = "bytes"))]
+mod tests_bytes {
+    use super::*;
+
+    #[test]
+    fn bytes_round_trip() {
+        let name = Name::from_str("www.example.com.").unwrap();
+        let buf = name.to_bytes().unwrap();
+        assert_eq!(&buf[..], b"\x03www\x07example\x03com\x00");
+
+        let (parsed, offset) = Name::from_wire_bytes(&buf, 0).unwrap();
+        assert!(parsed.case_matches(&name));
+        assert_eq!(offset, buf.len());
+
+        assert!(match Name::from_str("example.com").unwrap().to_bytes() {
+            Err(NameParseError::RelativeName(_)) => true,
+            _ => false
+        });
+    }
+}
+
+#[cfg(all(test, feature = "ipnetwork"))]
+mod tests_reverse_zones {
+    use super::*;
+
+    fn zones(net: &str) -> Vec<String> {
+        Name::reverse_zone_for_prefix(net.parse().unwrap()).iter()
+            .map(|v| v.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn reverse_zone_for_prefix_v4() {
+        assert_eq!(zones("192.0.2.0/24"), vec!["2.0.192.in-addr.arpa."]);
+        assert_eq!(zones("192.0.2.0/23"), vec!["2.0.192.in-addr.arpa.", "3.0.192.in-addr.arpa."]);
+        assert_eq!(zones("10.0.0.0/8"), vec!["10.in-addr.arpa."]);
+        assert_eq!(zones("0.0.0.0/0"), vec!["in-addr.arpa."]);
+        assert_eq!(zones("192.0.2.0/22").len(), 4);
+    }
+
+    #[test]
+    fn reverse_zone_for_prefix_v6() {
+        assert_eq!(zones("2001:db8:1234::/48"), vec!["4.3.2.1.8.b.d.0.1.0.0.2.ip6.arpa."]);
+        assert_eq!(zones("2001:db8::/31"), vec!["8.b.d.0.1.0.0.2.ip6.arpa.",
+            "9.b.d.0.1.0.0.2.ip6.arpa."]);
+    }
+}
+
+#[cfg(test)]
+mod tests_reverse {
+    use super::*;
+
+    #[test]
+    fn from_ip() {
+        let name = Name::from_ip("192.0.2.1".parse().unwrap());
+        assert_eq!(name.to_string(), "1.2.0.192.in-addr.arpa.");
+
+        let name = Name::from_ip("2001:db8::1".parse().unwrap());
+        assert_eq!(name.to_string(),
+                   "1.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.8.b.d.0.1.0.0.2.ip6.arpa.");
+    }
+
+    #[test]
+    fn reverse_components_v4() {
+        let name = Name::from_ip("192.0.2.1".parse().unwrap());
+        assert_eq!(name.reverse_components().unwrap(), vec![192, 0, 2, 1]);
+
+        let name = Name::from_str("2.0.192.IN-ADDR.arpa").unwrap();
+        assert_eq!(name.reverse_components().unwrap(), vec![192, 0, 2]);
+        let name = Name::from_str("0.10.in-addr.arpa.").unwrap();
+        assert_eq!(name.reverse_components().unwrap(), vec![10, 0]);
+        let name = Name::from_str("in-addr.arpa.").unwrap();
+        assert_eq!(name.reverse_components().unwrap(), Vec::<u8>::new());
+
+        for name in &["256.2.0.192.in-addr.arpa.", "01.2.0.192.in-addr.arpa.",
+            "1.1.2.0.192.in-addr.arpa.", "0/25.2.0.192.in-addr.arpa.", "a.in-addr.arpa."] {
+            assert!(Name::from_str(name).unwrap().reverse_components().is_none());
+        }
+    }
+
+    #[test]
+    fn reverse_components_v6() {
+        let name = Name::from_ip("2001:db8::1".parse().unwrap());
+        let mut expected = vec![2, 0, 0, 1, 0, 13, 11, 8];
+        expected.extend_from_slice(&[0; 23]);
+        expected.push(1);
+        assert_eq!(name.reverse_components().unwrap(), expected);
+
+        let name = Name::from_str("8.b.d.0.1.0.0.2.ip6.arpa.").unwrap();
+        assert_eq!(name.reverse_components().unwrap(), vec![2, 0, 0, 1, 0, 13, 11, 8]);
+
+        let name = unsafe { Name::from_bytes_ascii(b"B.ip6.arpa.").unwrap() };
+        assert!(name.reverse_components().is_none());
+        let name = Name::from_str(&format!("{}ip6.arpa.", "0.".repeat(33))).unwrap();
+        assert!(name.reverse_components().is_none());
+        assert!(Name::from_str("10.ip6.arpa.").unwrap().reverse_components().is_none());
+    }
+
+    #[test]
+    fn reverse_components_not_reverse() {
+        for name in &["www.example.com.", "arpa.", "example.arpa.", "1.2.ip6.example.", "."] {
+            assert!(Name::from_str(name).unwrap().reverse_components().is_none());
+        }
+        assert!(EMPTY.reverse_components().is_none());
+    }
+
+    #[test]
+    fn from_ip_classless() {
+        let name = Name::from_ip_classless(Ipv4Addr::new(192, 0, 2, 1), 25);
+        assert_eq!(name.to_string(), "0/25.2.0.192.in-addr.arpa.");
+
+        let name = Name::from_ip_classless(Ipv4Addr::new(192, 0, 2, 200), 25);
+        assert_eq!(name.to_string(), "128/25.2.0.192.in-addr.arpa.");
+
+        let name = Name::from_ip_classless(Ipv4Addr::new(192, 0, 2, 70), 27);
+        assert_eq!(name.to_string(), "64/27.2.0.192.in-addr.arpa.");
+
+        let name = Name::from_ip_classless(Ipv4Addr::new(192, 0, 2, 96), 27);
+        assert_eq!(name.to_string(), "96/27.2.0.192.in-addr.arpa.");
+    }
+
+    #[test]
+    fn from_ip_classless_octet_boundaries() {
+        let addr = Ipv4Addr::new(192, 0, 2, 1);
+        assert_eq!(Name::from_ip_classless(addr, 24).to_string(), "2.0.192.in-addr.arpa.");
+        assert_eq!(Name::from_ip_classless(addr, 32).to_string(), "1.2.0.192.in-addr.arpa.");
+        assert_eq!(Name::from_ip_classless(addr, 40).to_string(), "1.2.0.192.in-addr.arpa.");
+        assert_eq!(Name::from_ip_classless(addr, 0).to_string(), "in-addr.arpa.");
+    }
+}
+
+#[cfg(test)]
+mod tests_concatenate {
+    use super::*;
+
+    #[test]
+    fn concatenate() {
+        let www = Name::from_str("www").unwrap();
+        let name = www.concatenate(&Name::from_str("example.com.").unwrap()).unwrap();
+        assert_eq!(name.to_string(), "www.example.com.");
+
+        let name = www.concatenate(&Name::from_str("example.com").unwrap()).unwrap();
+        assert_eq!(name.to_string(), "www.example.com");
+
+        assert_eq!(www.concatenate(&ROOT).unwrap().to_string(), "www.");
+        assert_eq!(www.concatenate(&EMPTY).unwrap().to_string(), "www");
+        assert_eq!(EMPTY.concatenate(&www).unwrap().to_string(), "www");
+        assert_eq!(EMPTY.concatenate(&ROOT).unwrap(), *ROOT);
+    }
+
+    #[test]
+    fn complete_with_search() {
+        let search = [Name::from_str("corp.example.com.").unwrap(),
+            Name::from_str("example.com").unwrap()];
+
+        let candidates = Name::from_str("www").unwrap().complete_with_search(&search);
+        assert_eq!(candidates.iter().map(|v| v.to_string()).collect::<Vec<String>>(),
+                   vec!["www.corp.example.com.", "www.example.com.", "www."]);
+
+        let absolute = Name::from_str("www.example.org.").unwrap();
+        assert_eq!(absolute.complete_with_search(&search), vec![absolute.clone()]);
+    }
+
+    #[test]
+    fn try_relativize() {
+        let origin = Name::from_str("example.com.").unwrap();
+
+        let name = Name::from_ace("www.Example.COM.").unwrap();
+        assert_eq!(name.try_relativize(&origin).unwrap().to_string(), "www");
+        let name = Name::from_str("a.b.example.com.").unwrap();
+        assert_eq!(name.try_relativize(&origin).unwrap().to_string(), "a.b");
+        assert_eq!(name.try_relativize(&ROOT).unwrap().to_string(), "a.b.example.com");
+
+        let name = Name::from_str("a.b.example.com").unwrap();
+        assert_eq!(name.try_relativize(&Name::from_str("example.com").unwrap()).unwrap()
+                       .to_string(), "a.b");
+        assert_eq!(name.try_relativize(&EMPTY).unwrap(), name);
+    }
+
+    #[test]
+    fn is_at_or_below() {
+        let delegation = Name::from_str("sub.example.com.").unwrap();
+
+        // At
+        assert!(delegation.is_at_or_below(&delegation));
+        assert!(Name::from_ace("SUB.example.com.").unwrap().is_at_or_below(&delegation));
+        // Below
+        assert!(Name::from_str("ns1.sub.example.com.").unwrap().is_at_or_below(&delegation));
+        assert!(Name::from_str("a.b.sub.example.com.").unwrap().is_at_or_below(&delegation));
+        // Above and aside
+        assert!(!Name::from_str("example.com.").unwrap().is_at_or_below(&delegation));
+        assert!(!Name::from_str("ns1.example.com.").unwrap().is_at_or_below(&delegation));
+        assert!(!Name::from_str("sub.example.com").unwrap().is_at_or_below(&delegation));
+
+        assert!(delegation.is_at_or_below(&ROOT));
+        assert!(!delegation.is_at_or_below(&EMPTY));
+    }
+
+    #[test]
+    fn in_bailiwick() {
+        let zone = Name::from_str("example.com.").unwrap();
+
+        // The zone apex and the names below it
+        assert!(zone.in_bailiwick(&zone));
+        assert!(Name::from_ace("EXAMPLE.com.").unwrap().in_bailiwick(&zone));
+        assert!(Name::from_str("ns1.example.com.").unwrap().in_bailiwick(&zone));
+        assert!(Name::from_str("a.ns1.example.com.").unwrap().in_bailiwick(&zone));
+
+        // The parent, the siblings and the names merely ending with the same bytes
+        assert!(!Name::from_str("com.").unwrap().in_bailiwick(&zone));
+        assert!(!ROOT.in_bailiwick(&zone));
+        assert!(!Name::from_str("ns1.example.net.").unwrap().in_bailiwick(&zone));
+        assert!(!Name::from_str("badexample.com.").unwrap().in_bailiwick(&zone));
+        assert!(!Name::from_str("ns1.badexample.com.").unwrap().in_bailiwick(&zone));
+        assert!(!Name::from_str("example.com.evil.").unwrap().in_bailiwick(&zone));
+        assert!(!Name::from_str("ns1.example.com").unwrap().in_bailiwick(&zone));
+    }
+
+    #[test]
+    fn matches_any() {
+        let rules = [Name::from_str("ads.example.com").unwrap(),
+            Name::from_str("tracker.net.").unwrap()];
+
+        let name = Name::from_ace("x.ADS.example.com.").unwrap();
+        assert!(name.matches_any_suffix(&rules));
+        assert!(!name.matches_any_exact(&rules));
+
+        let name = Name::from_ace("Tracker.net").unwrap();
+        assert!(name.matches_any_suffix(&rules));
+        assert!(name.matches_any_exact(&rules));
+
+        for name in &["example.com", "www.example.com", "net", "xtracker.net"] {
+            let name = Name::from_str(name).unwrap();
+            assert!(!name.matches_any_suffix(&rules));
+            assert!(!name.matches_any_exact(&rules));
+        }
+        assert!(!name.matches_any_suffix(&[]));
+    }
+
+    #[test]
+    fn owner_within_zone() {
+        let zone = Name::from_str("example.com").unwrap();
+
+        let name = Name::from_str("www.example.com").unwrap();
+        assert_eq!(name.owner_within_zone(&zone).unwrap().to_string(), "www");
+        let name = Name::from_str("a.b.Example.com.").unwrap();
+        assert_eq!(name.owner_within_zone(&zone).unwrap().to_string(), "a.b");
+        let name = Name::from_str("www.example.com").unwrap();
+        assert_eq!(name.owner_within_zone(&Name::from_str("example.com.").unwrap()).unwrap()
+                       .to_string(), "www");
+    }
+
+    #[test]
+    fn owner_within_zone_apex() {
+        let zone = Name::from_str("example.com.").unwrap();
+        assert_eq!(zone.owner_within_zone(&zone).unwrap(), *EMPTY);
+        assert_eq!(Name::from_str("example.com").unwrap().owner_within_zone(&zone).unwrap(),
+                   *EMPTY);
+    }
+
+    #[test]
+    fn owner_within_zone_out_of_zone() {
+        let zone = Name::from_str("example.com").unwrap();
+        for name in &["www.example.org.", "com", "aexample.com", "example.com.au."] {
+            assert!(Name::from_str(name).unwrap().owner_within_zone(&zone).is_none());
+        }
+    }
+
+    #[test]
+    fn try_relativize_identity() {
+        let origin = Name::from_str("example.com.").unwrap();
+
+        let name = origin.try_relativize(&origin).unwrap();
+        assert!(name.is_empty_name());
+        assert_eq!(name, *EMPTY);
+        assert_eq!(ROOT.try_relativize(&ROOT).unwrap(), *EMPTY);
+    }
+
+    #[test]
+    fn try_relativize_not_subdomain() {
+        let origin = Name::from_str("example.com.").unwrap();
+
+        for name in &["www.example.org.", "com.", "aexample.com.", "www.example.com"] {
+            let name = Name::from_str(name).unwrap();
+            assert!(name.try_relativize(&origin).is_none());
+            assert_eq!(name.relativize(&origin), name);
+        }
+        assert!(EMPTY.try_relativize(&ROOT).is_none());
+    }
+
+    #[test]
+    fn make_absolute_with() {
+        let origin = Name::from_str("example.com.").unwrap();
+
+        let mut name = Name::from_str("www").unwrap();
+        name.make_absolute_with(&origin).unwrap();
+        assert_eq!(name.to_string(), "www.example.com.");
+
+        let mut name = Name::from_str("www").unwrap();
+        name.make_absolute_with(&ROOT).unwrap();
+        assert_eq!(name.to_string(), "www.");
+
+        let mut name = EMPTY.clone();
+        name.make_absolute_with(&origin).unwrap();
+        assert_eq!(name, origin);
+    }
+
+    #[test]
+    fn concat_into() {
+        let origin = Name::from_str("a-rather-long-origin-to-spill.example.com.").unwrap();
+        let mut out = EMPTY.clone();
+
+        for host in &["www", "mail", "", "x"] {
+            let host = Name::from_str(host).unwrap();
+            host.concat_into(&origin, &mut out).unwrap();
+            assert_eq!(out, host.concatenate(&origin).unwrap());
+        }
+
+        // The spilled capacity is kept for the shorter names
+        let capacity = out.heap_size();
+        assert!(capacity > 0);
+        Name::from_str("www").unwrap().concat_into(&ROOT, &mut out).unwrap();
+        assert_eq!(out.to_string(), "www.");
+        assert_eq!(out.heap_size(), capacity);
+
+        out.label_boundaries();
+        Name::from_str("a.b").unwrap().concat_into(&origin, &mut out).unwrap();
+        assert_eq!(out.label_boundaries().as_slice(), &[1, 3, 33, 41, 45]);
+    }
+
+    #[test]
+    fn concat_into_invalid() {
+        let mut out = Name::from_str("previous").unwrap();
+        assert!(match ROOT.concat_into(&EMPTY, &mut out) {
+            Err(NameParseError::AbsoluteName(_)) => true,
+            _ => false
+        });
+        assert_eq!(out, *EMPTY);
+
+        let name = Name::from_str(&vec!["x"; 100].join(".")).unwrap();
+        let origin = Name::from_str(&format!("{}.", vec!["y"; 100].join("."))).unwrap();
+        assert!(match name.concat_into(&origin, &mut out) {
+            Err(NameParseError::NameTooLarge(_)) => true,
+            _ => false
+        });
+        assert_eq!(out, *EMPTY);
+    }
+
+    #[test]
+    fn make_absolute_with_absolute() {
+        let mut name = Name::from_str("www.example.org.").unwrap();
+        name.make_absolute_with(&Name::from_str("example.com.").unwrap()).unwrap();
+        assert_eq!(name.to_string(), "www.example.org.");
+    }
+
+    #[test]
+    fn make_absolute_with_invalid() {
+        let mut name = Name::from_str("www").unwrap();
+        assert!(match name.make_absolute_with(&Name::from_str("example.com").unwrap()) {
+            Err(NameParseError::RelativeName(origin)) => origin == "example.com",
+            _ => false
+        });
+        assert_eq!(name.to_string(), "www");
+
+        let mut name = Name::from_str(&vec!["x"; 100].join(".")).unwrap();
+        let origin = Name::from_str(&format!("{}.", vec!["y"; 100].join("."))).unwrap();
+        assert!(match name.make_absolute_with(&origin) {
+            Err(NameParseError::NameTooLarge(_)) => true,
+            _ => false
+        });
+        assert_eq!(name.to_string(), vec!["x"; 100].join("."));
+    }
+
+    #[test]
+    fn concatenate_invalid() {
+        let absolute = Name::from_str("www.").unwrap();
+        assert!(match absolute.concatenate(&Name::from_str("example.com").unwrap()) {
+            Err(NameParseError::AbsoluteName(name)) => name == "www.",
+            _ => false
+        });
+
+        let long_name = Name::from_str(
+            std::iter::repeat("x.").take(65).collect::<String>().trim_end_matches('.')).unwrap();
+        assert!(match long_name.concatenate(&long_name) {
+            Err(NameParseError::NameTooLarge(_)) => true,
+            _ => false
+        });
+    }
+
+    #[test]
+    fn concatenate_single_trailing_dot() {
+        let www = Name::from_str("www").unwrap();
+        let name = www.concatenate(&Name::from_str("example.com.").unwrap()).unwrap();
+        assert_eq!(name.to_string(), "www.example.com.");
+        assert_eq!(name.wire_label_count(), name.label_count());
+
+        assert_eq!(EMPTY.concatenate(&EMPTY).unwrap(), *EMPTY);
+        assert_eq!(EMPTY.concatenate(&Name::from_str("com.").unwrap()).unwrap().to_string(),
+                   "com.");
+        assert!(www.concatenate(&ROOT).unwrap().concatenate(&ROOT).is_err());
+    }
+
+    #[test]
+    fn concatenate_malformed_raw() {
+        let www = Name::from_str("www").unwrap();
+
+        for other in &[&b".."[..], b".example.com.", b"example..com."] {
+            let other = unsafe { Name::from_bytes_raw(other).unwrap() };
+            assert!(match www.concatenate(&other) {
+                Err(NameParseError::EmptyLabel(_)) => true,
+                _ => false
+            });
+        }
+
+        let odd = unsafe { Name::from_bytes_raw(b"www..").unwrap() };
+        assert!(match odd.concatenate(&ROOT) {
+            Err(NameParseError::AbsoluteName(_)) => true,
+            _ => false
+        });
+    }
+
+    #[test]
+    fn concat_str() {
+        let host = Name::from_str("host").unwrap();
+        assert_eq!(host.concat_str("example.com.").unwrap().to_string(), "host.example.com.");
+
+        assert!(match Name::from_str("host.").unwrap().concat_str("example.com.") {
+            Err(NameParseError::AbsoluteName(_)) => true,
+            _ => false
+        });
+
+        assert!(match host.concat_str("example..com") {
+            Err(NameParseError::EmptyLabel(1)) => true,
+            _ => false
+        });
+    }
+
+    #[test]
+    fn qname_minimization_steps() {
+        let steps = Name::from_str("a.b.example.com").unwrap().qname_minimization_steps();
+        assert_eq!(
+            steps.iter().map(|v| v.to_string()).collect::<Vec<String>>(),
+            vec!["com", "example.com", "b.example.com", "a.b.example.com"]);
+
+        let steps = Name::from_str("a.b.example.com.").unwrap().qname_minimization_steps();
+        assert_eq!(
+            steps.iter().map(|v| v.to_string()).collect::<Vec<String>>(),
+            vec!["com.", "example.com.", "b.example.com.", "a.b.example.com."]);
+
+        assert!(ROOT.qname_minimization_steps().is_empty());
+        assert!(EMPTY.qname_minimization_steps().is_empty());
+    }
+
+    #[test]
+    fn map_labels_tld_swap() {
+        let swap = |label: Label| if &*label == b"com" { b"net".to_vec() } else { label.to_vec() };
+
+        let name = Name::from_str("example.com").unwrap().map_labels(swap).unwrap();
+        assert_eq!(name.to_string(), "example.net");
+
+        let name = Name::from_str("com.example.com.").unwrap().map_labels(swap).unwrap();
+        assert_eq!(name.to_string(), "net.example.net.");
+    }
+
+    #[test]
+    fn replace_tld() {
+        let name = Name::from_str("www.example.com").unwrap().replace_tld("test").unwrap();
+        assert_eq!(name.to_string(), "www.example.test");
+
+        let name = Name::from_str("example.com.").unwrap().replace_tld("test").unwrap();
+        assert_eq!(name.to_string(), "example.test.");
+        let name = Name::from_str("example.com").unwrap().replace_tld("test.").unwrap();
+        assert_eq!(name.to_string(), "example.test");
+        let name = Name::from_str("com.").unwrap().replace_tld("local").unwrap();
+        assert_eq!(name.to_string(), "local.");
+    }
+
+    #[test]
+    fn replace_tld_invalid() {
+        let name = Name::from_str("example.com").unwrap();
+        assert!(match name.replace_tld(&"x".repeat(64)) {
+            Err(NameParseError::LabelTooLong(label)) => label == "x".repeat(64),
+            _ => false
+        });
+        assert!(match name.replace_tld("") {
+            Err(NameParseError::EmptyLabel(1)) => true,
+            _ => false
+        });
+        assert!(match name.replace_tld("co.uk") {
+            Err(NameParseError::InvalidLabelByte(b'.', 10)) => true,
+            _ => false
+        });
+        assert!(match name.replace_tld("test..") {
+            Err(NameParseError::InvalidLabelByte(b'.', 12)) => true,
+            _ => false
+        });
+
+        // 253 characters, growing by 2 with the replacement
+        let long = format!("{}.{}", vec!["x".repeat(63); 3].join("."), "x".repeat(61));
+        let name = Name::from_str(&long).unwrap().replace_tld(&"y".repeat(63)).unwrap();
+        assert_eq!(name.len(), 255);
+        assert!(match Name::from_str(&format!("{}.", long)).unwrap().replace_tld(&"y".repeat(63)) {
+            Err(NameParseError::NameTooLarge(_)) => true,
+            _ => false
+        });
+        assert!(match ROOT.replace_tld("test") {
+            Err(NameParseError::EmptyName) => true,
+            _ => false
+        });
+    }
+
+    #[test]
+    fn map_labels_too_long() {
+        let name = Name::from_str("example.com").unwrap();
+        assert!(match name.map_labels(|label| label.repeat(10)) {
+            Err(NameParseError::LabelTooLong(label)) => label == "example".repeat(10),
+            _ => false
+        });
+    }
+
+    #[test]
+    fn ancestor() {
+        let name = Name::from_str("a.b.c.com").unwrap();
+        assert_eq!(name.ancestor(0).unwrap().to_string(), "a.b.c.com");
+        assert_eq!(name.ancestor(1).unwrap().to_string(), "b.c.com");
+        assert_eq!(name.ancestor(2).unwrap().to_string(), "c.com");
+        assert_eq!(name.ancestor(4).unwrap(), *EMPTY);
+        assert!(name.ancestor(5).is_none());
+
+        let name = Name::from_str("a.b.c.com.").unwrap();
+        assert_eq!(name.ancestor(2).unwrap().to_string(), "c.com.");
+        assert_eq!(name.ancestor(4).unwrap(), *ROOT);
+        assert!(name.ancestor(5).is_none());
+
+        assert_eq!(ROOT.ancestor(0).unwrap(), *ROOT);
+        assert!(ROOT.ancestor(1).is_none());
+    }
+
+    #[test]
+    fn split_left() {
+        let to_strings = |(prefix, suffix): (Name, Name)| (prefix.to_string(), suffix.to_string());
+
+        let name = Name::from_str("a.b.c.com").unwrap();
+        assert_eq!(to_strings(name.split_left(2).unwrap()), ("a.b".into(), "c.com".into()));
+        assert_eq!(to_strings(name.split_left(0).unwrap()), ("".into(), "a.b.c.com".into()));
+        assert_eq!(to_strings(name.split_left(4).unwrap()), ("a.b.c.com".into(), "".into()));
+
+        let name = Name::from_str("a.b.c.com.").unwrap();
+        assert_eq!(to_strings(name.split_left(1).unwrap()), ("a".into(), "b.c.com.".into()));
+        assert_eq!(to_strings(name.split_left(4).unwrap()), ("a.b.c.com".into(), ".".into()));
+    }
+
+    #[test]
+    fn owned_labels() {
+        let labels = Name::from_str("www.example.com.").unwrap().owned_labels();
+        assert_eq!(labels.iter().map(|v| v.to_string()).collect::<Vec<String>>(),
+                   vec!["www", "example", "com"]);
+        assert!(labels.iter().all(|v| !v.is_absolute() && v.label_count() == 1));
+        assert_eq!(labels[1], Name::from_str("example").unwrap());
+
+        assert!(ROOT.owned_labels().is_empty());
+        assert!(EMPTY.owned_labels().is_empty());
+    }
+
+    #[test]
+    fn split_left_out_of_range() {
+        assert!(Name::from_str("a.b.c.com").unwrap().split_left(5).is_none());
+        assert!(Name::from_str("a.b.c.com.").unwrap().split_left(5).is_none());
+        assert!(ROOT.split_left(1).is_none());
+        assert_eq!(ROOT.split_left(0).unwrap(), (EMPTY.clone(), ROOT.clone()));
+    }
+}
+
+#[cfg(test)]
+mod tests_display_cached {
+    use super::*;
+
+    #[test]
+    fn matches_to_text() {
+        let name = unsafe { Name::from_bytes_raw(b"a\\b c.example.com.").unwrap() };
+        let cached = name.display_cached();
+
+        assert_eq!(cached.to_string(), name.to_text(false));
+        assert_eq!(format!("[{}]", cached), format!("[{}]", name.to_text(false)));
+        assert!(std::ptr::eq(cached.name(), &name));
+    }
+
+    #[test]
+    fn computed_once() {
+        let name = Name::from_str("www.example.com.").unwrap();
+        let cached = DisplayCached::new(&name);
+        assert!(!cached.has_cached());
+
+        assert_eq!(cached.to_string(), "www.example.com.");
+        assert!(cached.has_cached());
+        let first = cached.text.borrow().as_ref().unwrap().as_ptr();
+
+        // The later uses write the same buffer instead of escaping the name again.
+        for _ in 0..10 {
+            assert_eq!(cached.to_string(), "www.example.com.");
+        }
+        assert!(std::ptr::eq(cached.text.borrow().as_ref().unwrap().as_ptr(), first));
+    }
+}
+
+#[cfg(test)]
+mod tests_name_ref {
+    use super::*;
+
+    #[test]
+    fn labels_outlive_name_ref() {
+        let name = Name::from_str("www.example.com.").unwrap();
+
+        let labels: Vec<Label>;
+        {
+            let name_ref = NameRef::from(&name);
+            labels = name_ref.labels().collect();
+            assert!(name_ref.is_absolute());
+        }
+        assert_eq!(labels, name.labels());
+
+        // Labels taken from a temporary view
+        let first = name.as_name_ref().labels().next().unwrap();
+        assert_eq!(first.to_string(), "www");
+    }
+
+    #[test]
+    fn split_ref() {
+        for name in &["a.b.c.com", "a.b.c.com.", ".", ""] {
+            let name = Name::from_str(name).unwrap();
+
+            for n in 0..6 {
+                let borrowed = name.split_ref(n)
+                    .map(|(prefix, suffix)| (prefix.to_string(), suffix.to_string()));
+                let owned = name.split_left(n)
+                    .map(|(prefix, suffix)| (prefix.to_string(), suffix.to_string()));
+                assert_eq!(borrowed, owned);
+            }
+        }
+
+        let name = Name::from_str("a.b.c.com.").unwrap();
+        let (prefix, suffix) = name.split_ref(2).unwrap();
+        assert_eq!((prefix.to_string(), suffix.to_string()), ("a.b".into(), "c.com.".into()));
+        assert!(!prefix.is_absolute() && suffix.is_absolute());
+        assert_eq!(suffix.to_name(), name.ancestor(2).unwrap());
+    }
+
+    #[test]
+    fn to_name() {
+        let name = Name::from_ace("WWW.example.com").unwrap();
+        let name_ref = name.as_name_ref();
+
+        assert!(name_ref.to_name().case_matches(&name));
+        assert!(!name_ref.is_absolute());
+        assert_eq!(name_ref.to_string(), "WWW.example.com");
+    }
+}
+
+#[cfg(test)]
+mod tests_case_preserving {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn equal_but_display_differently() {
+        let mixed = CasePreservingName::new(Name::from_ace("Example.COM").unwrap());
+        let lower = CasePreservingName::new(Name::from_ace("example.com").unwrap());
+
+        assert_eq!(mixed, lower);
+        assert_eq!(mixed.to_string(), "Example.COM");
+        assert_eq!(lower.to_string(), "example.com");
+        assert_eq!(mixed.canonical().to_string(), "example.com");
+        assert_eq!(mixed.original().to_string(), "Example.COM");
+
+        let mut set = HashSet::new();
+        set.insert(mixed);
+        assert!(set.contains(&lower));
+        assert!(!set.insert(lower));
+    }
+
+    #[test]
+    fn not_equal() {
+        let name = CasePreservingName::from(Name::from_ace("Example.COM").unwrap());
+        assert_ne!(name, CasePreservingName::from(Name::from_ace("Example.ORG").unwrap()));
+        assert_ne!(name, CasePreservingName::from(Name::from_ace("Example.COM.").unwrap()));
+    }
+}
+
+#[cfg(test)]
+mod tests_public_suffix {
+    use super::*;
+
+    fn psl() -> PublicSuffixList {
+        PublicSuffixList::from_rules(vec!["com", "uk", "co.uk"])
+    }
+
+    #[test]
+    fn zone_shard() {
+        let www = Name::from_str("www.example.com").unwrap();
+        let mail = Name::from_ace("mail.EXAMPLE.com.").unwrap();
+        let zone = Name::from_str("example.com").unwrap();
+
+        for shards in 1..64 {
+            let shard = www.zone_shard(&psl(), shards);
+            assert!(shard < shards);
+            assert_eq!(mail.zone_shard(&psl(), shards), shard);
+            assert_eq!(zone.zone_shard(&psl(), shards), shard);
+        }
+
+        // Different zones spread over the shards
+        let shards = (0..32)
+            .map(|v| Name::from_str(&format!("www.example{}.co.uk", v)).unwrap())
+            .map(|v| v.zone_shard(&psl(), 16))
+            .collect::<std::collections::HashSet<usize>>();
+        assert!(shards.len() > 1);
+    }
+
+    #[test]
+    fn zone_shard_stable() {
+        // Fixed by the FNV-1a hash of "example.com", 0x576846634e2714c6
+        let hash = Name::from_str("example.com").unwrap().canonical_hash_u64();
+        let name = Name::from_ace("WWW.Example.COM.").unwrap();
+        assert_eq!(name.zone_shard(&psl(), 1000), (hash % 1000) as usize);
+        assert_eq!(name.zone_shard(&psl(), 1000), 350);
+    }
+
+    #[test]
+    fn registrable_domain_multi_label_suffix() {
+        let name = Name::from_str("www.bbc.co.uk").unwrap();
+        assert_eq!(name.registrable_domain(&psl()).unwrap().to_string(), "bbc.co.uk");
+
+        let name = Name::from_str("www.bbc.co.uk.").unwrap();
+        assert_eq!(name.registrable_domain(&psl()).unwrap().to_string(), "bbc.co.uk.");
+    }
+
+    #[test]
+    fn registrable_domain_single_label_suffix() {
+        let name = Name::from_str("mail.google.com").unwrap();
+        assert_eq!(name.registrable_domain(&psl()).unwrap().to_string(), "google.com");
+
+        let name = Name::from_str("google.com").unwrap();
+        assert_eq!(name.registrable_domain(&psl()).unwrap().to_string(), "google.com");
+    }
+
+    #[test]
+    fn same_registrable_domain() {
+        let www = Name::from_str("www.example.com").unwrap();
+        assert!(www.same_registrable_domain(&Name::from_str("mail.example.com").unwrap(), &psl()));
+        assert!(www.same_registrable_domain(&Name::from_str("mail.example.com.").unwrap(), &psl()));
+        assert!(www.same_registrable_domain(&Name::from_str("example.com").unwrap(), &psl()));
+
+        assert!(!www.same_registrable_domain(&Name::from_str("example.org").unwrap(), &psl()));
+        assert!(!www.same_registrable_domain(&Name::from_str("other.com").unwrap(), &psl()));
+        assert!(!Name::from_str("com").unwrap()
+            .same_registrable_domain(&Name::from_str("com").unwrap(), &psl()));
+    }
+
+    #[test]
+    fn dissect() {
+        let to_strings = |(subdomain, label, suffix): (Name, Label, Name)|
+            (subdomain.to_string(), label.to_string(), suffix.to_string());
+
+        let name = Name::from_str("www.blog.example.co.uk").unwrap();
+        assert_eq!(to_strings(name.dissect(&psl()).unwrap()),
+                   ("www.blog".to_string(), "example".to_string(), "co.uk".to_string()));
+
+        let name = Name::from_str("www.example.com.").unwrap();
+        assert_eq!(to_strings(name.dissect(&psl()).unwrap()),
+                   ("www".to_string(), "example".to_string(), "com.".to_string()));
+
+        let name = Name::from_str("example.co.uk").unwrap();
+        let (subdomain, label, suffix) = name.dissect(&psl()).unwrap();
+        assert_eq!(subdomain, *EMPTY);
+        assert_eq!(label.to_string(), "example");
+        assert_eq!(suffix.to_string(), "co.uk");
+
+        assert!(Name::from_str("co.uk").unwrap().dissect(&psl()).is_none());
+        assert!(Name::from_str("com.").unwrap().dissect(&psl()).is_none());
+    }
+
+    #[test]
+    fn registrable_domain_of_suffix() {
+        assert!(Name::from_str("co.uk").unwrap().registrable_domain(&psl()).is_none());
+        assert!(Name::from_str("com.").unwrap().registrable_domain(&psl()).is_none());
+        assert!(ROOT.registrable_domain(&psl()).is_none());
+    }
+}
+
+#[cfg(test)]
+mod tests_wildcard {
+    use super::*;
+
+    #[test]
+    fn wildcard_base() {
+        let name = Name::from_str("*.example.com").unwrap();
+        assert!(name.is_wild());
+        assert_eq!(name.wildcard_base().unwrap().to_string(), "example.com");
+        assert_eq!(Name::from_str("*.a.example.com.").unwrap().wildcard_base().unwrap()
+                       .to_string(), "a.example.com.");
+        assert_eq!(Name::from_str("*.").unwrap().wildcard_base().unwrap(), *ROOT);
+    }
+
+    #[test]
+    fn wildcard_base_not_wildcard() {
+        for name in &["www.example.com", "a.*.example.com", "*a.example.com", "."] {
+            let name = Name::from_str(name).unwrap();
+            assert!(!name.is_wild());
+            assert!(name.wildcard_base().is_none());
+        }
+        assert!(EMPTY.wildcard_base().is_none());
+    }
+
+    #[test]
+    fn strip_leading_wildcards() {
+        let name = Name::from_str("*.example.com").unwrap();
+        assert_eq!(name.strip_leading_wildcards().to_string(), "example.com");
+        let name = Name::from_str("*.*.example.com.").unwrap();
+        assert_eq!(name.strip_leading_wildcards().to_string(), "example.com.");
+        assert_eq!(Name::from_str("*.").unwrap().strip_leading_wildcards(), *ROOT);
+    }
+
+    #[test]
+    fn strip_leading_wildcards_not_wildcard() {
+        for name in &["www.example.com", "a.*.example.com.", "*a.example.com", ".", ""] {
+            let name = Name::from_str(name).unwrap();
+            assert!(name.strip_leading_wildcards().case_matches(&name));
+        }
+    }
+
+    #[test]
+    fn matches_glob() {
+        let name = Name::from_str("web-1.example.com").unwrap();
+        assert!(name.matches_glob("web-?.example.com"));
+        assert!(name.matches_glob("WEB-?.Example.COM."));
+        assert!(name.matches_glob("*.example.com"));
+        assert!(name.matches_glob("*.*.com"));
+        assert!(name.matches_glob("???-?.*.com"));
+
+        assert!(!name.matches_glob("web-??.example.com"));
+        assert!(!name.matches_glob("web-?.example.org"));
+        assert!(!name.matches_glob("*.com"));
+        assert!(!name.matches_glob("*.*.*.example.com"));
+
+        assert!(Name::from_str("a.b.example.com").unwrap().matches_glob("*.*.example.com"));
+        assert!(!Name::from_str("a.example.com").unwrap().matches_glob("*.*.example.com"));
+        assert!(ROOT.matches_glob("."));
+        assert!(!ROOT.matches_glob("*"));
+    }
+
+    #[test]
+    fn matches_tls_identity() {
+        let name = Name::from_str("www.example.com").unwrap();
+        assert!(name.matches_tls_identity("www.example.com"));
+        assert!(name.matches_tls_identity("WWW.Example.COM."));
+        assert!(name.matches_tls_identity("*.example.com"));
+        assert!(Name::from_str("www.example.com.").unwrap().matches_tls_identity("*.example.com"));
+
+        assert!(!name.matches_tls_identity("www.example.org"));
+        assert!(!name.matches_tls_identity(""));
+        assert!(!Name::from_str("a.b.example.com").unwrap().matches_tls_identity("*.example.com"));
+        assert!(!Name::from_str("example.com").unwrap().matches_tls_identity("*.example.com"));
+    }
+
+    #[test]
+    fn matches_tls_identity_wildcard_rules() {
+        let name = Name::from_str("www.example.com").unwrap();
+
+        // Partial label wildcards
+        assert!(!name.matches_tls_identity("w*.example.com"));
+        assert!(!name.matches_tls_identity("*w.example.com"));
+        assert!(!name.matches_tls_identity("w*w.example.com"));
+
+        // Wildcards other than the whole leftmost label
+        assert!(!name.matches_tls_identity("www.*.com"));
+        assert!(!name.matches_tls_identity("*.*.com"));
+        assert!(!Name::from_str("example.com").unwrap().matches_tls_identity("*.com"));
+    }
+}
+
+#[cfg(test)]
+mod tests_labels {
+    use super::*;
+
+    #[test]
+    fn label_boundaries() {
+        let name = Name::from_str("a.bb.ccc").unwrap();
+        assert_eq!(name.label_boundaries().as_slice(), &[1, 4]);
+        assert_eq!(name.suffix_from(name.label_boundaries()[0] + 1).to_string(), "bb.ccc");
+
+        let name = Name::from_str("a.bb.ccc.").unwrap();
+        assert_eq!(name.label_boundaries().as_slice(), &[1, 4, 8]);
+        assert_eq!(name.suffix_from(name.label_boundaries()[2]), *ROOT);
+
+        assert_eq!(ROOT.label_boundaries().as_slice(), &[0]);
+        assert!(EMPTY.label_boundaries().is_empty());
+    }
+
+    #[test]
+    fn label_offsets() {
+        let name = Name::from_str("ab.cde.f").unwrap();
+        assert_eq!(
+            name.label_offsets().map(|(offset, label)| (offset, label.to_string()))
+                .collect::<Vec<(usize, String)>>(),
+            vec![(0, "ab".to_string()), (3, "cde".to_string()), (7, "f".to_string())]);
+
+        let name = Name::from_str("ab.cde.f.").unwrap();
+        assert_eq!(
+            name.label_offsets().map(|(offset, _)| offset).collect::<Vec<usize>>(),
+            vec![0, 3, 7, 9]);
+    }
+
+    #[test]
+    fn label_length_histogram() {
+        let names = ["www.example.com.", "mail.example.com", "a.b.", ".", ""].iter()
+            .map(|v| Name::from_str(v).unwrap())
+            .collect::<Vec<Name>>();
+
+        let histogram = super::label_length_histogram(&names);
+        assert_eq!(histogram[1], 2);
+        assert_eq!(histogram[3], 3);
+        assert_eq!(histogram[4], 1);
+        assert_eq!(histogram[7], 2);
+        assert_eq!(histogram.iter().sum::<usize>(), 8);
+
+        let long = Name::from_str(&"x".repeat(63)).unwrap();
+        assert_eq!(super::label_length_histogram(&[long])[63], 1);
+        assert_eq!(super::label_length_histogram(&[]), [0; 64]);
+    }
+}
+
+#[cfg(test)]
+mod tests_storage {
+    use super::*;
+
+    #[test]
+    fn heap_size() {
+        assert_eq!(Name::from_str("www.example.com.").unwrap().heap_size(), 0);
+        assert_eq!(ROOT.heap_size(), 0);
+
+        let name = Name::from_str(&format!("{}.example.com.", "a".repeat(40))).unwrap();
+        assert!(name.heap_size() >= name.len());
+
+        // The boundaries of more than 8 labels spill too once cached
+        let name = Name::from_str("a.b.c.d.e.f.g.h.i.j.").unwrap();
+        assert_eq!(name.heap_size(), 0);
+        name.label_boundaries();
+        assert!(name.heap_size() >= 10);
+    }
+
+    #[test]
+    fn boundary_cache() {
+        let name = Name::from_str("a.bb.ccc.").unwrap();
+        assert!(!name.has_boundary_cache());
+
+        assert_eq!(name.ancestor(1).unwrap().to_string(), "bb.ccc.");
+        assert!(name.has_boundary_cache());
+        assert_eq!(name.label_boundaries().as_slice(), &[1, 4, 8]);
+        assert_eq!(name.ancestor(2).unwrap().to_string(), "ccc.");
+
+        // Copies keep the cache, the new names start without it
+        assert!(name.clone().has_boundary_cache());
+        assert!(name.canonical_cow().has_boundary_cache());
+        assert!(!name.ancestor(1).unwrap().has_boundary_cache());
+
+        let mut name = Name::from_str("www").unwrap();
+        assert_eq!(name.label_boundaries().as_slice(), &[] as &[usize]);
+        name.make_absolute_with(&Name::from_str("example.com.").unwrap()).unwrap();
+        assert_eq!(name.label_boundaries().as_slice(), &[3, 11, 15]);
+        assert_eq!(name.label_count(), 3);
+    }
+
+    #[test]
+    fn boundary_cache_debug() {
+        let name = Name::from_str("a.bb.ccc.").unwrap();
+        let before = format!("{:?}", name);
+
+        // Counting the separators scans the bytes and leaves the cache alone
+        assert_eq!(name.wire_label_count(), 3);
+        assert!(!name.has_boundary_cache());
+
+        assert_eq!(name.label_count(), 3);
+        assert!(name.has_boundary_cache());
+        assert_eq!(format!("{:?}", name), before);
+    }
+
+    #[test]
+    fn boundary_cache_labels() {
+        for text in &["a.bb.ccc.", "a.bb.ccc", "www", ".", ""] {
+            let name = Name::from_str(text).unwrap();
+            assert!(!name.has_boundary_cache());
+
+            let labels = name.labels();
+            assert!(name.has_boundary_cache());
+            assert_eq!(labels.iter().map(|v| v.to_vec()).collect::<Vec<Vec<u8>>>(),
+                       text.as_bytes().split(|v| *v == b'.').map(|v| v.to_vec())
+                           .collect::<Vec<Vec<u8>>>());
+            // Served from the cache on the repeated accesses
+            assert_eq!(name.labels(), labels);
+        }
+
+        let name = Name::from_str("a.bb.ccc.").unwrap();
+        assert_eq!(name.label_count(), 3);
+        assert!(name.has_boundary_cache());
+        assert_eq!(name.owned_labels().iter().map(|v| v.to_string()).collect::<Vec<String>>(),
+                   vec!["a", "bb", "ccc"]);
     }
 }
 
 #[cfg(test)]
 mod tests_layout {
     use super::*;
-}
\ No newline at end of file
+}
@@ -0,0 +1,124 @@
+use std::collections::HashSet;
+use std::convert::Infallible;
+use std::str::FromStr;
+
+/// A public suffix list as published at https://publicsuffix.org/list/
+///
+/// No list is bundled with this crate as the list changes frequently. The list is
+/// supplied by the user either rule by rule or by parsing the `public_suffix_list.dat`
+/// file format.
+///
+/// Rules are matched case-insensitively against the ascii(punycode) form of the labels,
+/// so the rules for internationalized suffixes have to be added in their punycode form.
+///
+/// When no rule matches a name, the implicit `*` rule applies. That is, the top level
+/// label is considered as the public suffix.
+#[derive(Debug, Default, Clone)]
+pub struct PublicSuffixList {
+    rules: HashSet<String>,
+    wildcards: HashSet<String>,
+    exceptions: HashSet<String>,
+}
+
+impl PublicSuffixList {
+    /// Creates a list with no rules other than the implicit `*` rule.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a list from the given rules.
+    pub fn from_rules<'a>(rules: impl IntoIterator<Item=&'a str>) -> Self {
+        let mut list = Self::new();
+        for rule in rules {
+            list.add_rule(rule);
+        }
+        list
+    }
+
+    /// Adds a rule to the list.
+    ///
+    /// 1. `co.uk` adds a normal rule
+    /// 2. `*.ck` adds a wildcard rule
+    /// 3. `!www.ck` adds an exception rule
+    pub fn add_rule(&mut self, rule: &str) {
+        let rule = rule.trim().trim_end_matches('.').to_ascii_lowercase();
+
+        if let Some(exception) = rule.strip_prefix('!') {
+            self.exceptions.insert(exception.to_string());
+        } else if let Some(wildcard) = rule.strip_prefix("*.") {
+            self.wildcards.insert(wildcard.to_string());
+        } else if !rule.is_empty() {
+            self.rules.insert(rule);
+        }
+    }
+
+    /// Returns the number of rightmost labels that form the public suffix of the given
+    /// labels.
+    ///
+    /// The labels are expected to be ordered leftmost first and to not include the root
+    /// label.
+    pub fn suffix_len(&self, labels: &[&[u8]]) -> usize {
+        let total = labels.len();
+        // suffixes[i] is the suffix made of the labels from i till the end
+        let suffixes: Vec<String> = (0..total)
+            .map(|i| {
+                let mut suffix = labels[i..].join(&b'.');
+                suffix.make_ascii_lowercase();
+                String::from_utf8_lossy(&suffix).into_owned()
+            })
+            .collect();
+
+        // Exception rules take priority over all the other rules
+        for (i, suffix) in suffixes.iter().enumerate() {
+            if self.exceptions.contains(suffix) {
+                return total - i - 1;
+            }
+        }
+
+        for (i, suffix) in suffixes.iter().enumerate() {
+            if self.rules.contains(suffix) {
+                return total - i;
+            }
+            if i + 1 < total && self.wildcards.contains(&suffixes[i + 1]) {
+                return total - i;
+            }
+        }
+
+        // The implicit '*' rule
+        total.min(1)
+    }
+}
+
+/// Parses the `public_suffix_list.dat` file format.
+///
+/// Lines starting with `//` are comments and empty lines are ignored. Only the first
+/// whitespace separated word of a line is considered as the rule.
+impl FromStr for PublicSuffixList {
+    type Err = Infallible;
+
+    fn from_str(list: &str) -> Result<Self, Self::Err> {
+        Ok(Self::from_rules(
+            list.lines()
+                .filter_map(|line| line.split_whitespace().next())
+                .filter(|rule| !rule.starts_with("//"))))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn suffix_len() {
+        let psl: PublicSuffixList = "// comment\n\ncom\nuk\nco.uk\n*.ck\n!www.ck\n"
+            .parse().unwrap();
+
+        assert_eq!(psl.suffix_len(&[b"www", b"bbc", b"co", b"uk"]), 2);
+        assert_eq!(psl.suffix_len(&[b"www", b"Example", b"COM"]), 1);
+        assert_eq!(psl.suffix_len(&[b"a", b"b", b"ck"]), 2);
+        assert_eq!(psl.suffix_len(&[b"www", b"ck"]), 1);
+        // Implicit '*' rule
+        assert_eq!(psl.suffix_len(&[b"example", b"unknown"]), 1);
+        assert_eq!(psl.suffix_len(&[]), 0);
+    }
+}
@@ -0,0 +1,117 @@
+use std::cmp::Ordering;
+use std::iter::FromIterator;
+
+use super::name::Name;
+
+/// A set of domain names matching the names equal to or under any of them, like a
+/// blocklist.
+///
+/// The names are kept in the canonical DNSSEC order (RFC 4034 section 6.1), where a name
+/// is directly followed by the names under it. So a lookup is a single binary search for
+/// the closest preceding name. Labels are matched case-insensitively and the
+/// absoluteness of the names is ignored, same as [`super::trie::DomainTrie`].
+///
+/// The set holds no name covered by another of its names, as only the membership
+/// matters. Use `DomainTrie` instead when a value has to be kept per name.
+#[derive(Debug, Default, Clone)]
+pub struct SuffixSet {
+    names: Vec<Name>,
+}
+
+/// Checks whether the name is equal to or under the suffix, ignoring the absoluteness.
+fn covers(suffix: &Name, name: &Name) -> bool {
+    let len = suffix.labels().iter().filter(|v| !v.is_empty()).count();
+    name.common_suffix_len(suffix) == len
+}
+
+impl SuffixSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Checks whether the name is equal to or under any of the names in the set.
+    pub fn contains(&self, name: &Name) -> bool {
+        // Only the absoluteness can make an entry with the same labels compare greater
+        let position = self.names.partition_point(
+            |v| v.canonical_cmp(name) != Ordering::Greater || covers(v, name));
+
+        position > 0 && covers(&self.names[position - 1], name)
+    }
+
+    /// Returns the names in the canonical DNSSEC order.
+    pub fn iter(&self) -> impl Iterator<Item=&Name> {
+        self.names.iter()
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+}
+
+/// Builds the set dropping the redundant names already covered by another name of the
+/// set, like `ads.example.com` along with `example.com`.
+///
+/// The names are sorted in the canonical order, which places the names under a name
+/// right after it, and are then collapsed in a single pass.
+impl FromIterator<Name> for SuffixSet {
+    fn from_iter<T: IntoIterator<Item=Name>>(iter: T) -> Self {
+        let mut sorted: Vec<Name> = iter.into_iter().collect();
+        sorted.sort_by(Name::canonical_cmp);
+
+        let mut names: Vec<Name> = Vec::with_capacity(sorted.len());
+        for name in sorted {
+            if names.last().is_none_or(|v| !covers(v, &name)) {
+                names.push(name);
+            }
+        }
+
+        names.shrink_to_fit();
+        SuffixSet { names }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+
+    fn set(names: &[&str]) -> SuffixSet {
+        names.iter().map(|v| Name::from_str(v).unwrap()).collect()
+    }
+
+    fn name(name: &str) -> Name {
+        Name::from_str(name).unwrap()
+    }
+
+    #[test]
+    fn redundant_names_dropped() {
+        let set = set(&["ads.example.com", "example.org", "example.com", "x.ads.example.com",
+            "Example.COM.", "tracker.net"]);
+
+        assert_eq!(set.iter().map(|v| v.to_string()).collect::<Vec<String>>(),
+                   vec!["example.com", "tracker.net", "example.org"]);
+        assert_eq!(set.len(), 3);
+    }
+
+    #[test]
+    fn contains() {
+        let set = set(&["example.com.", "ads.example.org", "net"]);
+
+        assert!(set.contains(&name("example.com")));
+        assert!(set.contains(&name("www.Example.com.")));
+        assert!(set.contains(&name("x.ads.example.org")));
+        assert!(set.contains(&name("anything.net.")));
+        assert!(!set.contains(&name("example.org")));
+        assert!(!set.contains(&name("www.example.org")));
+        assert!(!set.contains(&name("com")));
+        assert!(!set.contains(&name("aexample.com")));
+        assert!(!SuffixSet::new().contains(&name("example.com")));
+    }
+}
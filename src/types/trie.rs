@@ -0,0 +1,173 @@
+use std::collections::BTreeMap;
+
+use super::name::Name;
+
+/// A map from domain names to values supporting the longest suffix match.
+///
+/// The names are stored in a trie keyed on the labels from right to left, so finding the
+/// closest enclosing domain of a name is a single walk down the trie. Labels are matched
+/// case-insensitively and the absoluteness of the names is ignored, i.e. `example.com`
+/// and `Example.COM.` are the same key.
+#[derive(Debug)]
+pub struct DomainTrie<V> {
+    root: Node<V>,
+    len: usize,
+}
+
+#[derive(Debug)]
+struct Node<V> {
+    entry: Option<(Name, V)>,
+    children: BTreeMap<Vec<u8>, Node<V>>,
+}
+
+impl<V> Default for Node<V> {
+    fn default() -> Self {
+        Node {
+            entry: None,
+            children: BTreeMap::new(),
+        }
+    }
+}
+
+impl<V> Default for DomainTrie<V> {
+    fn default() -> Self {
+        DomainTrie {
+            root: Node::default(),
+            len: 0,
+        }
+    }
+}
+
+/// Returns the labels of the name from right to left, lowercased and without the root
+/// label.
+fn trie_key(name: &Name) -> impl Iterator<Item=Vec<u8>> + '_ {
+    name.labels()
+        .into_iter()
+        .rev()
+        .filter(|v| !v.is_empty())
+        .map(|v| v.to_ascii_lowercase())
+}
+
+impl<V> DomainTrie<V> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Inserts the value for the name, returning the previous value of the name if any.
+    pub fn insert(&mut self, name: Name, value: V) -> Option<V> {
+        let mut node = &mut self.root;
+        for label in trie_key(&name) {
+            node = node.children.entry(label).or_default();
+        }
+
+        let previous = node.entry.replace((name, value)).map(|(_, v)| v);
+        if previous.is_none() {
+            self.len += 1;
+        }
+        previous
+    }
+
+    /// Returns the value of the exact name.
+    pub fn get(&self, name: &Name) -> Option<&V> {
+        let mut node = &self.root;
+        for label in trie_key(name) {
+            node = node.children.get(&label)?;
+        }
+        node.entry.as_ref().map(|(_, v)| v)
+    }
+
+    /// Returns the value of the longest inserted name which is equal to or a suffix of the
+    /// given name.
+    ///
+    /// With `example.com -> 1` and `sub.example.com -> 2` inserted, `x.sub.example.com`
+    /// matches `2` and `x.example.com` matches `1`.
+    pub fn longest_suffix_match(&self, name: &Name) -> Option<&V> {
+        let mut node = &self.root;
+        let mut matched = node.entry.as_ref();
+
+        for label in trie_key(name) {
+            match node.children.get(&label) {
+                Some(child) => node = child,
+                None => break
+            }
+            matched = node.entry.as_ref().or(matched);
+        }
+        matched.map(|(_, v)| v)
+    }
+
+    /// Returns the inserted names and their values in the canonical DNSSEC order.
+    pub fn iter(&self) -> impl Iterator<Item=(&Name, &V)> {
+        let mut entries = Vec::with_capacity(self.len);
+        let mut pending = vec![&self.root];
+
+        while let Some(node) = pending.pop() {
+            if let Some((name, value)) = &node.entry {
+                entries.push((name, value));
+            }
+            pending.extend(node.children.values().rev());
+        }
+        entries.into_iter()
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+
+    fn name(name: &str) -> Name {
+        Name::from_str(name).unwrap()
+    }
+
+    #[test]
+    fn longest_suffix_match() {
+        let mut trie = DomainTrie::new();
+        assert_eq!(trie.insert(name("example.com"), 1), None);
+        assert_eq!(trie.insert(name("sub.example.com"), 2), None);
+
+        assert_eq!(trie.longest_suffix_match(&name("x.sub.example.com")), Some(&2));
+        assert_eq!(trie.longest_suffix_match(&name("sub.example.com.")), Some(&2));
+        assert_eq!(trie.longest_suffix_match(&name("x.example.com")), Some(&1));
+        assert_eq!(trie.longest_suffix_match(&name("example.com")), Some(&1));
+        assert_eq!(trie.longest_suffix_match(&name("com")), None);
+        assert_eq!(trie.longest_suffix_match(&name("example.org")), None);
+    }
+
+    #[test]
+    fn case_insensitive() {
+        let mut trie = DomainTrie::new();
+        trie.insert(unsafe { Name::from_bytes_ascii(b"Example.COM").unwrap() }, 1);
+
+        assert_eq!(trie.longest_suffix_match(&name("www.example.com")), Some(&1));
+        assert_eq!(trie.get(&name("example.com.")), Some(&1));
+        assert_eq!(trie.insert(name("example.com"), 2), Some(1));
+        assert_eq!(trie.len(), 1);
+    }
+
+    #[test]
+    fn iter() {
+        let mut trie = DomainTrie::new();
+        assert!(trie.is_empty());
+        trie.insert(name("b.example.com"), 3);
+        trie.insert(name("example.org"), 4);
+        trie.insert(name("example.com"), 1);
+        trie.insert(name("a.example.com"), 2);
+
+        assert_eq!(
+            trie.iter().map(|(k, v)| (k.to_string(), *v)).collect::<Vec<(String, i32)>>(),
+            vec![("example.com".to_string(), 1), ("a.example.com".to_string(), 2),
+                 ("b.example.com".to_string(), 3), ("example.org".to_string(), 4)]);
+        assert_eq!(trie.len(), 4);
+    }
+}